@@ -0,0 +1,22 @@
+extern crate protoc_grpcio;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const PROTO_ROOT: &str = "../../_proto";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}/catalogs.proto", PROTO_ROOT);
+
+    let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR for build scripts");
+    protoc_grpcio::compile_grpc_protos(&["catalogs.proto"], &[PROTO_ROOT], &out_dir)
+        .expect("failed to compile catalogs.proto");
+
+    // The generated files start with inner attributes, which `include!` does not accept,
+    // so they are declared as modules by path instead and left as they were generated.
+    let modules = ["catalogs", "catalogs_grpc"].iter()
+        .map(|module| format!("#[path = {:?}]\npub mod {};\n", Path::new(&out_dir).join(format!("{}.rs", module)), module))
+        .collect::<String>();
+    fs::write(Path::new(&out_dir).join("modules.rs"), modules).expect("failed to write generated modules");
+}
@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use protobuf::RepeatedField;
+
+use ignition_catalogs_wasm::catalog::{CountedOptions, ItemCount, OptionsByFamily};
+use ignition_catalogs_wasm::catalog_builder::{CatalogAssembly, CatalogExclusionRule, CatalogInclusionRule, RuleTarget};
+use ignition_catalogs_wasm::errors::{CatalogsError, StatusClass};
+use ignition_catalogs_wasm::types::{Family as FamilyId, Item, ItemStatus};
+
+use proto::catalogs::{Catalog, CatalogExclusionsRule, CatalogInclusionsRule, CatalogOptions, CreateCatalogRequest, Family, FamilyOptions, ItemOption, ItemOption_Status, UpdateCatalogRequest};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConversionError {
+    DuplicateFamily { family: FamilyId },
+    AttributeTarget { attribute: String, value: String },
+    UnusedStatus { item: Item },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::DuplicateFamily { family } => write!(f, "family '{}' is listed more than once", family),
+            ConversionError::AttributeTarget { attribute, value } =>
+                write!(f, "rules targeting attribute '{}' = '{}' have no protobuf form", attribute, value),
+            ConversionError::UnusedStatus { item } => write!(f, "item '{}' has no status", item),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+/// Attribute targets come from catalogs built elsewhere, so they are a precondition of the catalog rather than a bad request.
+impl CatalogsError for ConversionError {
+    fn code(&self) -> &'static str {
+        match self {
+            ConversionError::DuplicateFamily { .. } => "DUPLICATE_FAMILY",
+            ConversionError::AttributeTarget { .. } => "ATTRIBUTE_TARGET",
+            ConversionError::UnusedStatus { .. } => "UNUSED_STATUS",
+        }
+    }
+
+    fn status(&self) -> StatusClass {
+        match self {
+            ConversionError::DuplicateFamily { .. } | ConversionError::UnusedStatus { .. } => StatusClass::InvalidArgument,
+            ConversionError::AttributeTarget { .. } => StatusClass::FailedPrecondition,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a CreateCatalogRequest> for CatalogAssembly {
+    type Error = ConversionError;
+
+    fn try_from(req: &'a CreateCatalogRequest) -> Result<Self, Self::Error> {
+        to_assembly(req.get_families(), req.get_exclusions(), req.get_inclusions())
+    }
+}
+
+impl<'a> TryFrom<&'a UpdateCatalogRequest> for CatalogAssembly {
+    type Error = ConversionError;
+
+    fn try_from(req: &'a UpdateCatalogRequest) -> Result<Self, Self::Error> {
+        to_assembly(req.get_families(), req.get_exclusions(), req.get_inclusions())
+    }
+}
+
+/// Fills in the families and rules of a catalog; ids, timestamps and state are left to the caller.
+impl<'a> TryFrom<&'a CatalogAssembly> for Catalog {
+    type Error = ConversionError;
+
+    fn try_from(assembly: &'a CatalogAssembly) -> Result<Self, Self::Error> {
+        let families = assembly.families().iter()
+            .map(|(family_id, items)| {
+                let mut family = Family::new();
+                family.set_family_id(family_id.clone());
+                family.set_items(RepeatedField::from_vec(items.clone()));
+                family
+            })
+            .collect();
+        let exclusion_rules = assembly.exclusions().iter()
+            .map(CatalogExclusionsRule::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let inclusion_rules = assembly.inclusions().iter()
+            .map(CatalogInclusionsRule::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut catalog = Catalog::new();
+        catalog.set_families(RepeatedField::from_vec(families));
+        catalog.set_exclusion_rules(RepeatedField::from_vec(exclusion_rules));
+        catalog.set_inclusion_rules(RepeatedField::from_vec(inclusion_rules));
+        Ok(catalog)
+    }
+}
+
+impl<'a> From<&'a CatalogExclusionsRule> for CatalogExclusionRule {
+    fn from(rule: &'a CatalogExclusionsRule) -> Self {
+        CatalogExclusionRule::new(to_targets(rule.get_conditions()), to_targets(rule.get_exclusions()))
+    }
+}
+
+impl<'a> From<&'a CatalogInclusionsRule> for CatalogInclusionRule {
+    fn from(rule: &'a CatalogInclusionsRule) -> Self {
+        CatalogInclusionRule::new(to_targets(rule.get_conditions()), to_targets(rule.get_inclusions()))
+    }
+}
+
+impl<'a> TryFrom<&'a CatalogExclusionRule> for CatalogExclusionsRule {
+    type Error = ConversionError;
+
+    fn try_from(rule: &'a CatalogExclusionRule) -> Result<Self, Self::Error> {
+        let mut exclusion_rule = CatalogExclusionsRule::new();
+        exclusion_rule.set_conditions(to_items(rule.conditions())?);
+        exclusion_rule.set_exclusions(to_items(rule.exclusions())?);
+        Ok(exclusion_rule)
+    }
+}
+
+impl<'a> TryFrom<&'a CatalogInclusionRule> for CatalogInclusionsRule {
+    type Error = ConversionError;
+
+    fn try_from(rule: &'a CatalogInclusionRule) -> Result<Self, Self::Error> {
+        let mut inclusion_rule = CatalogInclusionsRule::new();
+        inclusion_rule.set_conditions(to_items(rule.conditions())?);
+        inclusion_rule.set_inclusions(to_items(rule.inclusions())?);
+        Ok(inclusion_rule)
+    }
+}
+
+impl<'a> From<&'a ItemStatus<Item>> for ItemOption {
+    fn from(status: &'a ItemStatus<Item>) -> Self {
+        let item_status = match status {
+            ItemStatus::Available(_) => ItemOption_Status::AVAILABLE,
+            ItemStatus::Selected(_) => ItemOption_Status::SELECTED,
+            ItemStatus::Excluded(_) => ItemOption_Status::EXCLUDED,
+            ItemStatus::Required(_) => ItemOption_Status::REQUIRED,
+            ItemStatus::UserExcluded(_) => ItemOption_Status::USER_EXCLUDED,
+            ItemStatus::Implied(_) => ItemOption_Status::IMPLIED,
+        };
+
+        let mut option = ItemOption::new();
+        option.set_item_id(status.item().clone());
+        option.set_item_status(item_status);
+        option
+    }
+}
+
+impl<'a> From<&'a ItemCount> for ItemOption {
+    fn from(item: &'a ItemCount) -> Self {
+        let mut option = ItemOption::from(item.status());
+        option.set_outfit_count(item.count() as u64);
+        option
+    }
+}
+
+/// `UNUSED` is the proto3 default and never describes a real item, so it does not convert.
+impl<'a> TryFrom<&'a ItemOption> for ItemStatus<Item> {
+    type Error = ConversionError;
+
+    fn try_from(option: &'a ItemOption) -> Result<Self, Self::Error> {
+        let item = option.get_item_id().to_string();
+
+        match option.get_item_status() {
+            ItemOption_Status::UNUSED => Err(ConversionError::UnusedStatus { item }),
+            ItemOption_Status::AVAILABLE => Ok(ItemStatus::Available(item)),
+            ItemOption_Status::SELECTED => Ok(ItemStatus::Selected(item)),
+            ItemOption_Status::EXCLUDED => Ok(ItemStatus::Excluded(item)),
+            ItemOption_Status::REQUIRED => Ok(ItemStatus::Required(item)),
+            ItemOption_Status::USER_EXCLUDED => Ok(ItemStatus::UserExcluded(item)),
+            ItemOption_Status::IMPLIED => Ok(ItemStatus::Implied(item)),
+        }
+    }
+}
+
+impl<'a> From<&'a OptionsByFamily> for CatalogOptions {
+    fn from(options: &'a OptionsByFamily) -> Self {
+        to_catalog_options(options.iter()
+            .map(|(family_id, statuses)| (family_id, statuses.iter().map(ItemOption::from).collect())))
+    }
+}
+
+impl<'a> From<&'a CountedOptions> for CatalogOptions {
+    fn from(options: &'a CountedOptions) -> Self {
+        let mut catalog_options = to_catalog_options(options.options().iter()
+            .map(|(family_id, items)| (family_id, items.iter().map(ItemOption::from).collect())));
+        catalog_options.set_total_outfits(options.total() as u64);
+        catalog_options
+    }
+}
+
+impl<'a> TryFrom<&'a CatalogOptions> for OptionsByFamily {
+    type Error = ConversionError;
+
+    fn try_from(options: &'a CatalogOptions) -> Result<Self, Self::Error> {
+        options.get_options().iter()
+            .map(|family| {
+                let statuses = family.get_options().iter()
+                    .map(ItemStatus::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok((family.get_family_id().to_string(), statuses))
+            })
+            .collect()
+    }
+}
+
+fn to_assembly(families: &[Family], exclusions: &[CatalogExclusionsRule], inclusions: &[CatalogInclusionsRule]) -> Result<CatalogAssembly, ConversionError> {
+    let mut family_items = BTreeMap::new();
+    for family in families {
+        let family_id = family.get_family_id().to_string();
+        if family_items.contains_key(&family_id) {
+            return Err(ConversionError::DuplicateFamily { family: family_id });
+        }
+        family_items.insert(family_id, family.get_items().to_vec());
+    }
+
+    let exclusions = exclusions.iter().map(CatalogExclusionRule::from).collect();
+    let inclusions = inclusions.iter().map(CatalogInclusionRule::from).collect();
+
+    Ok(CatalogAssembly::new(family_items, exclusions, inclusions))
+}
+
+fn to_targets(items: &[Item]) -> Vec<RuleTarget> {
+    items.iter().cloned().map(RuleTarget::from).collect()
+}
+
+/// The proto only knows rules over items, so attribute targets cannot be represented.
+fn to_items(targets: &[RuleTarget]) -> Result<RepeatedField<Item>, ConversionError> {
+    targets.iter()
+        .map(|target| match target {
+            RuleTarget::Item(item) => Ok(item.clone()),
+            RuleTarget::Attribute { attribute, value } => Err(ConversionError::AttributeTarget {
+                attribute: attribute.clone(),
+                value: value.clone(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(RepeatedField::from_vec)
+}
+
+fn to_catalog_options<'a, I>(families: I) -> CatalogOptions
+    where I: Iterator<Item=(&'a FamilyId, Vec<ItemOption>)>
+{
+    let families = families
+        .map(|(family_id, items)| {
+            let mut family = FamilyOptions::new();
+            family.set_family_id(family_id.clone());
+            family.set_options(RepeatedField::from_vec(items));
+            family
+        })
+        .collect();
+
+    let mut catalog_options = CatalogOptions::new();
+    catalog_options.set_options(RepeatedField::from_vec(families));
+    catalog_options
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use protobuf::RepeatedField;
+
+    use ignition_catalogs_wasm::catalog::OptionsByFamily;
+    use ignition_catalogs_wasm::catalog_builder::{CatalogAssembly, CatalogExclusionRule, RuleTarget};
+    use ignition_catalogs_wasm::types::{Item, ItemStatus};
+
+    use proto::catalogs::{Catalog, CatalogExclusionsRule, CatalogInclusionsRule, CatalogOptions, CreateCatalogRequest, Family, ItemOption, ItemOption_Status};
+
+    use super::ConversionError;
+
+    fn strings(items: &[&str]) -> RepeatedField<String> {
+        RepeatedField::from_vec(items.iter().map(|&item| item.into()).collect())
+    }
+
+    fn family(family_id: &str, items: &[&str]) -> Family {
+        let mut family = Family::new();
+        family.set_family_id(family_id.into());
+        family.set_items(strings(items));
+        family
+    }
+
+    fn create_request(families: Vec<Family>) -> CreateCatalogRequest {
+        let mut exclusion = CatalogExclusionsRule::new();
+        exclusion.set_conditions(strings(&["shirts:red"]));
+        exclusion.set_exclusions(strings(&["pants:jeans"]));
+
+        let mut inclusion = CatalogInclusionsRule::new();
+        inclusion.set_conditions(strings(&["shirts:blue"]));
+        inclusion.set_inclusions(strings(&["pants:slacks"]));
+
+        let mut req = CreateCatalogRequest::new();
+        req.set_families(RepeatedField::from_vec(families));
+        req.set_exclusions(RepeatedField::from_vec(vec![exclusion]));
+        req.set_inclusions(RepeatedField::from_vec(vec![inclusion]));
+        req
+    }
+
+    #[test]
+    fn create_request_round_trips_through_assembly() {
+        let req = create_request(vec![
+            family("pants", &["pants:jeans", "pants:slacks"]),
+            family("shirts", &["shirts:red", "shirts:blue"]),
+        ]);
+
+        let assembly = CatalogAssembly::try_from(&req).unwrap();
+        let catalog = Catalog::try_from(&assembly).unwrap();
+
+        assert_eq!(req.get_families(), catalog.get_families());
+        assert_eq!(req.get_exclusions(), catalog.get_exclusion_rules());
+        assert_eq!(req.get_inclusions(), catalog.get_inclusion_rules());
+    }
+
+    #[test]
+    fn duplicate_families_do_not_convert() {
+        let req = create_request(vec![
+            family("shirts", &["shirts:red"]),
+            family("shirts", &["shirts:blue"]),
+        ]);
+
+        assert_eq!(
+            ConversionError::DuplicateFamily { family: "shirts".into() },
+            CatalogAssembly::try_from(&req).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn attribute_targets_do_not_convert() {
+        let rule = CatalogExclusionRule::new(
+            vec![RuleTarget::Attribute { attribute: "color".into(), value: "red".into() }],
+            vec![RuleTarget::Item("pants:jeans".into())],
+        );
+
+        assert_eq!(
+            ConversionError::AttributeTarget { attribute: "color".into(), value: "red".into() },
+            CatalogExclusionsRule::try_from(&rule).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn item_statuses_round_trip_through_item_options() {
+        let statuses = vec![
+            ItemStatus::Available(Item::from("shirts:red")),
+            ItemStatus::Selected(Item::from("shirts:red")),
+            ItemStatus::Excluded(Item::from("shirts:red")),
+            ItemStatus::Required(Item::from("shirts:red")),
+            ItemStatus::UserExcluded(Item::from("shirts:red")),
+            ItemStatus::Implied(Item::from("shirts:red")),
+        ];
+
+        for status in statuses {
+            let option = ItemOption::from(&status);
+            assert_eq!(Ok(status), ItemStatus::try_from(&option));
+        }
+    }
+
+    #[test]
+    fn unused_status_does_not_convert() {
+        let mut option = ItemOption::new();
+        option.set_item_id("shirts:red".into());
+        option.set_item_status(ItemOption_Status::UNUSED);
+
+        assert_eq!(
+            Err(ConversionError::UnusedStatus { item: "shirts:red".into() }),
+            ItemStatus::try_from(&option)
+        );
+    }
+
+    #[test]
+    fn options_round_trip_through_catalog_options() {
+        let options: OptionsByFamily = btreemap! {
+            "pants".into() => vec![ItemStatus::Required(Item::from("pants:jeans"))],
+            "shirts".into() => vec![ItemStatus::Available(Item::from("shirts:red")), ItemStatus::Excluded(Item::from("shirts:blue"))],
+        };
+
+        let catalog_options = CatalogOptions::from(&options);
+
+        assert_eq!(Ok(options), OptionsByFamily::try_from(&catalog_options));
+    }
+}
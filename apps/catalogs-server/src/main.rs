@@ -0,0 +1,54 @@
+extern crate base64;
+extern crate chrono;
+extern crate futures;
+extern crate grpcio;
+extern crate ignition_catalogs_wasm;
+#[cfg(test)]
+#[macro_use]
+extern crate maplit;
+extern crate protobuf;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use std::env;
+use std::sync::Arc;
+
+use futures::Future;
+use futures::future;
+use grpcio::{Environment, ServerBuilder};
+
+use ignition_catalogs_wasm::storage::{CatalogStore, FileCatalogStore, MemoryCatalogStore};
+
+use proto::catalogs_grpc::create_catalog_manager;
+use service::CatalogManagerService;
+
+mod conversions;
+mod proto;
+mod service;
+mod state;
+
+fn main() {
+    let port = env::var("PORT").ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(8080);
+
+    // Catalogs are kept in memory unless a data directory is configured.
+    let store: Arc<dyn CatalogStore> = match env::var("CATALOG_DATA_DIR") {
+        Ok(root) => Arc::new(FileCatalogStore::new(root)),
+        Err(_) => Arc::new(MemoryCatalogStore::default()),
+    };
+
+    let service = create_catalog_manager(CatalogManagerService::new(store));
+    let mut server = ServerBuilder::new(Arc::new(Environment::new(1)))
+        .register_service(service)
+        .bind("0.0.0.0", port)
+        .build()
+        .expect("failed to build gRPC server");
+
+    server.start();
+    eprintln!("Started gRPC service on 0.0.0.0:{}", port);
+
+    future::empty::<(), ()>().wait().unwrap();
+}
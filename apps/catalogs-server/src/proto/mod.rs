@@ -0,0 +1,3 @@
+#![allow(bare_trait_objects, dead_code, non_camel_case_types, non_snake_case, non_upper_case_globals, unused_imports, unused_results)]
+
+include!(concat!(env!("OUT_DIR"), "/modules.rs"));
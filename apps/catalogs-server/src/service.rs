@@ -0,0 +1,525 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use chrono::Utc;
+use futures::Future;
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use protobuf::RepeatedField;
+
+use ignition_catalogs_wasm::catalog::{CatalogError, CatalogState};
+use ignition_catalogs_wasm::catalog_builder::{build_catalog, CatalogAssembly, CatalogBuilderError};
+use ignition_catalogs_wasm::errors::{CatalogsError, StatusClass};
+use ignition_catalogs_wasm::storage::{CatalogRecord, CatalogStore, Expected, StoreError};
+use ignition_catalogs_wasm::types::Item;
+
+use conversions::ConversionError;
+use proto::catalogs::{Catalog, CatalogOptions, CreateCatalogRequest, ListCatalogsRequest, ListCatalogsResponse, RetrieveCatalogOptionsRequest, RetrieveCatalogRequest, UpdateCatalogRequest};
+use proto::catalogs_grpc::CatalogManager;
+use state::ClientState;
+
+#[derive(Debug, PartialEq)]
+pub struct ServiceError {
+    code: RpcStatusCode,
+    message: String,
+}
+
+impl ServiceError {
+    fn new(code: RpcStatusCode, message: &str) -> Self {
+        ServiceError { code, message: message.into() }
+    }
+
+    /// Engine errors carry their own status class, and lead their message with their stable code.
+    fn from_engine<E: CatalogsError>(err: &E) -> Self {
+        let code = match err.status() {
+            StatusClass::InvalidArgument => RpcStatusCode::InvalidArgument,
+            StatusClass::FailedPrecondition => RpcStatusCode::FailedPrecondition,
+            StatusClass::NotFound => RpcStatusCode::NotFound,
+            StatusClass::AlreadyExists => RpcStatusCode::AlreadyExists,
+            StatusClass::Internal => RpcStatusCode::Internal,
+        };
+
+        ServiceError::new(code, &format!("{}: {}", err.code(), err))
+    }
+}
+
+impl From<StoreError> for ServiceError {
+    fn from(err: StoreError) -> Self {
+        ServiceError::from_engine(&err)
+    }
+}
+
+impl From<CatalogBuilderError> for ServiceError {
+    fn from(err: CatalogBuilderError) -> Self {
+        ServiceError::from_engine(&err)
+    }
+}
+
+impl From<ConversionError> for ServiceError {
+    fn from(err: ConversionError) -> Self {
+        ServiceError::from_engine(&err)
+    }
+}
+
+impl From<CatalogError> for ServiceError {
+    fn from(err: CatalogError) -> Self {
+        ServiceError::from_engine(&err)
+    }
+}
+
+/// Implements `CatalogManager` on top of the engine, keeping catalogs in a `CatalogStore`.
+#[derive(Clone)]
+pub struct CatalogManagerService {
+    store: Arc<dyn CatalogStore>,
+}
+
+impl CatalogManagerService {
+    pub fn new(store: Arc<dyn CatalogStore>) -> Self {
+        CatalogManagerService { store }
+    }
+
+    fn list(&self, req: &ListCatalogsRequest) -> Result<ListCatalogsResponse, ServiceError> {
+        let project_id = required("project_id", req.get_project_id())?;
+
+        let catalogs = self.store.list(project_id)?
+            .iter()
+            .map(to_catalog)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut res = ListCatalogsResponse::new();
+        res.set_project_id(project_id.into());
+        res.set_catalogs(RepeatedField::from_vec(catalogs));
+        Ok(res)
+    }
+
+    fn retrieve(&self, req: &RetrieveCatalogRequest) -> Result<Catalog, ServiceError> {
+        let project_id = required("project_id", req.get_project_id())?;
+        let catalog_id = required("catalog_id", req.get_catalog_id())?;
+
+        let record = match req.get_version() {
+            0 => self.store.get(project_id, catalog_id)?,
+            version => self.store.get_version(project_id, catalog_id, version)?,
+        };
+        to_catalog(&record)
+    }
+
+    fn retrieve_options(&self, req: &RetrieveCatalogOptionsRequest) -> Result<CatalogOptions, ServiceError> {
+        let project_id = required("project_id", req.get_project_id())?;
+        let catalog_id = required("catalog_id", req.get_catalog_id())?;
+
+        let previous = match req.get_state() {
+            "" => ClientState::default_state(project_id, catalog_id),
+            state => ClientState::decode(state)
+                .ok_or_else(|| ServiceError::new(RpcStatusCode::InvalidArgument, "Bad Catalog State"))?,
+        };
+        if previous.project_id() != project_id || previous.catalog_id() != catalog_id {
+            return Err(ServiceError::new(RpcStatusCode::InvalidArgument, "Catalog State belongs to another catalog"));
+        }
+
+        // States keep using the version they were built from, even after the catalog is updated,
+        // unless they ask to be rebased from that version onto the latest one.
+        let latest = self.store.get(project_id, catalog_id)?;
+        let (record, previous, rebased) = match previous.version() {
+            Some(version) if version != latest.version() && req.get_rebase() => {
+                let recorded = self.store.get_version(project_id, catalog_id, version)?;
+                let rebased = recorded.state().clone()
+                    .with_choices(previous.selections().to_vec(), previous.exclusions().to_vec())
+                    .rebase_onto(latest.state())?;
+                let previous = ClientState::new(
+                    project_id,
+                    catalog_id,
+                    rebased.state().selections().to_vec(),
+                    rebased.state().exclusions().to_vec(),
+                );
+
+                (latest, previous, Some(rebased))
+            }
+            Some(version) if version != latest.version() => (self.store.get_version(project_id, catalog_id, version)?, previous, None),
+            _ => (latest, previous, None),
+        };
+
+        let selections = CatalogState::chain(previous.selections(), req.get_selections());
+        let exclusions = CatalogState::chain(previous.exclusions(), req.get_exclusions());
+
+        let mut catalog_options = options(&record, &selections, &exclusions)?;
+        if let Some(rebased) = rebased {
+            let dropped_selections = rebased.missing_selections().iter()
+                .chain(rebased.conflicting_selections())
+                .cloned()
+                .collect();
+
+            catalog_options.set_dropped_selections(RepeatedField::from_vec(dropped_selections));
+            catalog_options.set_dropped_exclusions(RepeatedField::from_vec(rebased.missing_exclusions().to_vec()));
+        }
+        Ok(catalog_options)
+    }
+
+    fn create(&self, req: &CreateCatalogRequest) -> Result<CatalogOptions, ServiceError> {
+        let project_id = required("project_id", req.get_project_id())?;
+        let catalog_id = required("catalog_id", req.get_catalog_id())?;
+
+        let assembly = CatalogAssembly::try_from(req)?;
+        let record = build(project_id, catalog_id, assembly)?;
+        let record = self.store.put(record, Expected::Missing)?;
+
+        options(&record, &[], &[])
+    }
+
+    fn update(&self, req: &UpdateCatalogRequest) -> Result<CatalogOptions, ServiceError> {
+        let project_id = required("project_id", req.get_project_id())?;
+        let catalog_id = required("catalog_id", req.get_catalog_id())?;
+
+        // Updates never create a catalog, and fail if another update lands first.
+        let current = self.store.get(project_id, catalog_id)?;
+
+        let assembly = CatalogAssembly::try_from(req)?;
+        let record = build(project_id, catalog_id, assembly)?;
+        let record = self.store.put(record, Expected::Version(current.version()))?;
+
+        options(&record, &[], &[])
+    }
+}
+
+impl CatalogManager for CatalogManagerService {
+    fn list_catalogs(&mut self, ctx: RpcContext, req: ListCatalogsRequest, sink: UnarySink<ListCatalogsResponse>) {
+        respond(ctx, sink, self.list(&req));
+    }
+
+    fn retrieve_catalog(&mut self, ctx: RpcContext, req: RetrieveCatalogRequest, sink: UnarySink<Catalog>) {
+        respond(ctx, sink, self.retrieve(&req));
+    }
+
+    fn retrieve_catalog_options(&mut self, ctx: RpcContext, req: RetrieveCatalogOptionsRequest, sink: UnarySink<CatalogOptions>) {
+        respond(ctx, sink, self.retrieve_options(&req));
+    }
+
+    fn create_catalog(&mut self, ctx: RpcContext, req: CreateCatalogRequest, sink: UnarySink<CatalogOptions>) {
+        respond(ctx, sink, self.create(&req));
+    }
+
+    fn update_catalog(&mut self, ctx: RpcContext, req: UpdateCatalogRequest, sink: UnarySink<CatalogOptions>) {
+        respond(ctx, sink, self.update(&req));
+    }
+}
+
+fn respond<T>(ctx: RpcContext, sink: UnarySink<T>, result: Result<T, ServiceError>) {
+    let reply = match result {
+        Ok(res) => sink.success(res),
+        Err(err) => sink.fail(RpcStatus::new(err.code, Some(err.message))),
+    };
+
+    ctx.spawn(reply.map_err(|err| eprintln!("failed to reply: {:?}", err)));
+}
+
+fn required<'a>(field: &str, value: &'a str) -> Result<&'a str, ServiceError> {
+    if value.is_empty() {
+        Err(ServiceError::new(RpcStatusCode::InvalidArgument, &format!("{} is required", field)))
+    } else {
+        Ok(value)
+    }
+}
+
+fn build(project_id: &str, catalog_id: &str, assembly: CatalogAssembly) -> Result<CatalogRecord, ServiceError> {
+    let catalog = build_catalog(assembly.clone())?;
+    let state = CatalogState::from_catalog(catalog);
+
+    Ok(CatalogRecord::new(project_id, catalog_id, Utc::now().to_rfc3339(), assembly, state))
+}
+
+fn options(record: &CatalogRecord, selections: &[Item], exclusions: &[Item]) -> Result<CatalogOptions, ServiceError> {
+    let (options, state) = record.state().clone().counted_options(selections, exclusions)?;
+    let client_state = ClientState::new(
+        record.project_id(),
+        record.catalog_id(),
+        state.selections().to_vec(),
+        state.exclusions().to_vec(),
+    ).with_version(record.version());
+
+    let mut catalog_options = CatalogOptions::from(&options);
+    catalog_options.set_state(client_state.encode());
+    Ok(catalog_options)
+}
+
+fn to_catalog(record: &CatalogRecord) -> Result<Catalog, ServiceError> {
+    let mut catalog = Catalog::try_from(record.assembly())?;
+    catalog.set_project_id(record.project_id().into());
+    catalog.set_catalog_id(record.catalog_id().into());
+    catalog.set_created(record.created().into());
+    catalog.set_default_state(ClientState::default_state(record.project_id(), record.catalog_id()).with_version(record.version()).encode());
+    catalog.set_version(record.version());
+    catalog.set_hash(record.hash().into());
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpcio::RpcStatusCode;
+    use protobuf::RepeatedField;
+
+    use ignition_catalogs_wasm::storage::MemoryCatalogStore;
+
+    use proto::catalogs::{CatalogExclusionsRule, CatalogOptions, CreateCatalogRequest, Family, ItemOption_Status, RetrieveCatalogOptionsRequest, RetrieveCatalogRequest, UpdateCatalogRequest};
+
+    use state::ClientState;
+
+    use super::{CatalogManagerService, ServiceError};
+
+    fn service() -> CatalogManagerService {
+        CatalogManagerService::new(Arc::new(MemoryCatalogStore::default()))
+    }
+
+    fn family(family_id: &str, items: &[&str]) -> Family {
+        let mut family = Family::new();
+        family.set_family_id(family_id.into());
+        family.set_items(RepeatedField::from_vec(items.iter().map(|&item| item.into()).collect()));
+        family
+    }
+
+    fn strings(items: &[&str]) -> RepeatedField<String> {
+        RepeatedField::from_vec(items.iter().map(|&item| item.into()).collect())
+    }
+
+    fn create_request() -> CreateCatalogRequest {
+        let mut exclusion = CatalogExclusionsRule::new();
+        exclusion.set_conditions(strings(&["shirts:red"]));
+        exclusion.set_exclusions(strings(&["pants:jeans"]));
+
+        let mut req = CreateCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_families(RepeatedField::from_vec(vec![
+            family("shirts", &["shirts:red", "shirts:blue"]),
+            family("pants", &["pants:jeans", "pants:slacks"]),
+        ]));
+        req.set_exclusions(RepeatedField::from_vec(vec![exclusion]));
+        req
+    }
+
+    fn statuses(options: &CatalogOptions, family_id: &str) -> Vec<(String, ItemOption_Status)> {
+        let mut statuses = options.get_options().iter()
+            .filter(|family| family.get_family_id() == family_id)
+            .flat_map(|family| family.get_options().iter())
+            .map(|option| (option.get_item_id().into(), option.get_item_status()))
+            .collect::<Vec<_>>();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    #[test]
+    fn create_catalog_returns_default_options() {
+        let options = service().create(&create_request()).unwrap();
+
+        assert_eq!(3, options.get_total_outfits());
+        assert_eq!(
+            vec![
+                (String::from("shirts:blue"), ItemOption_Status::AVAILABLE),
+                (String::from("shirts:red"), ItemOption_Status::AVAILABLE),
+            ],
+            statuses(&options, "shirts")
+        );
+    }
+
+    #[test]
+    fn create_catalog_fails_when_catalog_exists() {
+        let service = service();
+        service.create(&create_request()).unwrap();
+
+        let err = service.create(&create_request()).unwrap_err();
+        assert_eq!(RpcStatusCode::AlreadyExists, err.code);
+    }
+
+    #[test]
+    fn create_catalog_reports_build_errors() {
+        let mut req = create_request();
+        req.set_families(RepeatedField::new());
+
+        let err = service().create(&req).unwrap_err();
+        assert_eq!(
+            ServiceError::new(RpcStatusCode::InvalidArgument, "EMPTY_CATALOG: families are required to build a catalog"),
+            err
+        );
+    }
+
+    #[test]
+    fn create_catalog_reports_conversion_errors() {
+        let mut req = create_request();
+        req.set_families(RepeatedField::from_vec(vec![
+            family("shirts", &["shirts:red"]),
+            family("shirts", &["shirts:blue"]),
+        ]));
+
+        let err = service().create(&req).unwrap_err();
+        assert_eq!(
+            ServiceError::new(RpcStatusCode::InvalidArgument, "DUPLICATE_FAMILY: family 'shirts' is listed more than once"),
+            err
+        );
+    }
+
+    #[test]
+    fn retrieve_options_applies_selections_on_top_of_state() {
+        let service = service();
+        let created = service.create(&create_request()).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_state(created.get_state().into());
+        req.set_selections(strings(&["shirts:red"]));
+        let options = service.retrieve_options(&req).unwrap();
+
+        assert_eq!(1, options.get_total_outfits());
+        assert_eq!(
+            vec![
+                (String::from("pants:jeans"), ItemOption_Status::EXCLUDED),
+                (String::from("pants:slacks"), ItemOption_Status::IMPLIED),
+            ],
+            statuses(&options, "pants")
+        );
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_state(options.get_state().into());
+        let options = service.retrieve_options(&req).unwrap();
+
+        assert_eq!(1, options.get_total_outfits());
+    }
+
+    #[test]
+    fn update_catalog_replaces_catalog() {
+        let service = service();
+        service.create(&create_request()).unwrap();
+
+        let mut req = UpdateCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_families(RepeatedField::from_vec(vec![family("shirts", &["shirts:red", "shirts:blue"])]));
+        let options = service.update(&req).unwrap();
+        assert_eq!(2, options.get_total_outfits());
+
+        let mut req = RetrieveCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        let catalog = service.retrieve(&req).unwrap();
+
+        assert_eq!(1, catalog.get_families().len());
+        assert!(catalog.get_exclusion_rules().is_empty());
+    }
+
+    #[test]
+    fn update_catalog_fails_for_unknown_catalog() {
+        let mut req = UpdateCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("winter".into());
+        req.set_families(RepeatedField::from_vec(vec![family("shirts", &["shirts:red", "shirts:blue"])]));
+
+        let err = service().update(&req).unwrap_err();
+        assert_eq!(RpcStatusCode::NotFound, err.code);
+    }
+
+    #[test]
+    fn states_keep_the_version_they_were_built_from() {
+        let service = service();
+        let created = service.create(&create_request()).unwrap();
+
+        let mut req = UpdateCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_families(RepeatedField::from_vec(vec![family("shirts", &["shirts:red", "shirts:blue"])]));
+        service.update(&req).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_state(created.get_state().into());
+        assert_eq!(3, service.retrieve_options(&req).unwrap().get_total_outfits());
+
+        let mut req = RetrieveCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        assert_eq!(2, service.retrieve(&req).unwrap().get_version());
+
+        req.set_version(1);
+        let catalog = service.retrieve(&req).unwrap();
+        assert_eq!(1, catalog.get_version());
+        assert_eq!(2, catalog.get_families().len());
+    }
+
+    #[test]
+    fn stale_states_can_be_rebased_onto_the_latest_version() {
+        let service = service();
+        service.create(&create_request()).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_selections(strings(&["shirts:red", "pants:slacks"]));
+        let selected = service.retrieve_options(&req).unwrap();
+
+        let mut req = UpdateCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_families(RepeatedField::from_vec(vec![family("shirts", &["shirts:red", "shirts:blue"])]));
+        service.update(&req).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_state(selected.get_state().into());
+        req.set_rebase(true);
+        let options = service.retrieve_options(&req).unwrap();
+
+        assert_eq!(1, options.get_total_outfits());
+        assert_eq!(&["pants:slacks".to_string()], options.get_dropped_selections());
+        assert!(options.get_dropped_exclusions().is_empty());
+        assert_eq!(
+            vec![
+                (String::from("shirts:blue"), ItemOption_Status::EXCLUDED),
+                (String::from("shirts:red"), ItemOption_Status::SELECTED),
+            ],
+            statuses(&options, "shirts")
+        );
+    }
+
+    #[test]
+    fn rebasing_a_state_from_an_unknown_version_fails() {
+        let service = service();
+        service.create(&create_request()).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("summer".into());
+        req.set_state(ClientState::default_state("project", "summer").with_version(7).encode());
+        req.set_rebase(true);
+
+        let err = service.retrieve_options(&req).unwrap_err();
+        assert_eq!(RpcStatusCode::NotFound, err.code);
+    }
+
+    #[test]
+    fn retrieve_options_rejects_a_state_from_another_catalog() {
+        let service = service();
+        let created = service.create(&create_request()).unwrap();
+
+        let mut req = RetrieveCatalogOptionsRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("winter".into());
+        req.set_state(created.get_state().into());
+
+        assert_eq!(
+            ServiceError::new(RpcStatusCode::InvalidArgument, "Catalog State belongs to another catalog"),
+            service.retrieve_options(&req).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn retrieve_catalog_fails_for_unknown_catalog() {
+        let mut req = RetrieveCatalogRequest::new();
+        req.set_project_id("project".into());
+        req.set_catalog_id("winter".into());
+
+        let err = service().retrieve(&req).unwrap_err();
+        assert_eq!(RpcStatusCode::NotFound, err.code);
+    }
+}
@@ -0,0 +1,98 @@
+use ignition_catalogs_wasm::types::Item;
+
+/// The state handed to clients between requests, in the same shape the Node service uses:
+/// base64 encoded JSON naming the catalog and the choices made so far.
+/// States from this server also name the catalog version they were built from.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientState {
+    project_id: String,
+    catalog_id: String,
+    selections: Vec<Item>,
+    exclusions: Vec<Item>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+}
+
+impl ClientState {
+    pub fn new(project_id: &str, catalog_id: &str, selections: Vec<Item>, exclusions: Vec<Item>) -> Self {
+        ClientState {
+            project_id: project_id.into(),
+            catalog_id: catalog_id.into(),
+            selections,
+            exclusions,
+            version: None,
+        }
+    }
+
+    pub fn with_version(self, version: u64) -> Self {
+        ClientState { version: Some(version), ..self }
+    }
+
+    pub fn default_state(project_id: &str, catalog_id: &str) -> Self {
+        Self::new(project_id, catalog_id, vec![], vec![])
+    }
+
+    pub fn decode(state: &str) -> Option<Self> {
+        let bytes = base64::decode(state).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn encode(&self) -> String {
+        base64::encode(&serde_json::to_vec(self).unwrap())
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    pub fn catalog_id(&self) -> &str {
+        &self.catalog_id
+    }
+
+    pub fn selections(&self) -> &[Item] {
+        &self.selections
+    }
+
+    pub fn exclusions(&self) -> &[Item] {
+        &self.exclusions
+    }
+
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientState;
+
+    #[test]
+    fn state_round_trips_through_encoding() {
+        let state = ClientState::new("project", "summer", vec!["shirts:red".into()], vec![]);
+
+        assert_eq!(Some(state.clone()), ClientState::decode(&state.encode()));
+    }
+
+    #[test]
+    fn state_is_compatible_with_the_node_service() {
+        let encoded = base64::encode(r#"{"projectId":"project","catalogId":"summer","selections":[],"exclusions":["pants:jeans"]}"#);
+
+        assert_eq!(
+            Some(ClientState::new("project", "summer", vec![], vec!["pants:jeans".into()])),
+            ClientState::decode(&encoded)
+        );
+    }
+
+    #[test]
+    fn versioned_state_round_trips_through_encoding() {
+        let state = ClientState::default_state("project", "summer").with_version(3);
+
+        assert_eq!(Some(3), ClientState::decode(&state.encode()).and_then(|state| state.version()));
+    }
+
+    #[test]
+    fn malformed_state_is_not_decoded() {
+        assert_eq!(None, ClientState::decode("not a state"));
+    }
+}
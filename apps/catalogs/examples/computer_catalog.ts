@@ -176,7 +176,7 @@ function findComputerBuild(options: CatalogOptions): string[] {
     return ([] as ItemOption[])
         .concat(...part_matrix)
         .map((item: ItemOption) => item.toObject())
-        .filter((item: ItemOption.AsObject) => item.itemStatus == ItemOption.Status.SELECTED || item.itemStatus == ItemOption.Status.REQUIRED)
+        .filter((item: ItemOption.AsObject) => item.itemStatus == ItemOption.Status.SELECTED || item.itemStatus == ItemOption.Status.REQUIRED || item.itemStatus == ItemOption.Status.IMPLIED)
         .map((item: ItemOption.AsObject) => item.itemId);
 }
 
@@ -116,7 +116,7 @@ function findRecipe(options: CatalogOptions): string[] {
     return ([] as ItemOption[])
         .concat(...ingredient_matrix)
         .map((item: ItemOption) => item.toObject())
-        .filter((item: ItemOption.AsObject) => item.itemStatus == ItemOption.Status.SELECTED || item.itemStatus == ItemOption.Status.REQUIRED)
+        .filter((item: ItemOption.AsObject) => item.itemStatus == ItemOption.Status.SELECTED || item.itemStatus == ItemOption.Status.REQUIRED || item.itemStatus == ItemOption.Status.IMPLIED)
         .map((item: ItemOption.AsObject) => item.itemId);
 }
 
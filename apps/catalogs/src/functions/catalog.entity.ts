@@ -2,7 +2,7 @@ import Datastore = require("@google-cloud/datastore");
 import {DatastorePayload} from "@google-cloud/datastore/entity";
 
 import {fromTaskEither} from "@ignition/nomad";
-import {CatalogExclusionRule, CatalogFamilies, CatalogInclusionRule, CatalogToken} from "@ignition/catalogs";
+import {CatalogExclusionRule, CatalogFamilies, CatalogInclusionRule, CatalogToken, Item} from "@ignition/catalogs";
 
 import {asks, Reader} from "fp-ts/lib/Reader";
 import {fromLeft, taskEither, tryCatch} from "fp-ts/lib/TaskEither";
@@ -13,13 +13,13 @@ import {timed} from "../infrastructure/effects";
 
 export type CatalogAssembly = {
     readonly families: CatalogFamilies;
-    readonly exclusions: CatalogExclusionRule[];
-    readonly inclusions: CatalogInclusionRule[];
+    readonly exclusions: CatalogExclusionRule<Item>[];
+    readonly inclusions: CatalogInclusionRule<Item>[];
 };
 
 export type CatalogRules = {
-    readonly exclusions: CatalogExclusionRule[];
-    readonly inclusions: CatalogInclusionRule[];
+    readonly exclusions: CatalogExclusionRule<Item>[];
+    readonly inclusions: CatalogInclusionRule<Item>[];
 }
 
 export type CatalogEntity = {
@@ -1,5 +1,5 @@
 import {nomadRTE} from "@ignition/nomad";
-import {Item, ItemStatus} from "@ignition/catalogs";
+import {Item, ItemCount} from "@ignition/catalogs";
 
 import {CatalogOptions, FamilyOptions, ItemOption, RetrieveCatalogOptionsRequest} from "../../generated/catalogs_pb";
 import {status} from "grpc";
@@ -36,8 +36,9 @@ function fromRequest(req: RetrieveCatalogOptionsRequest): CatalogsResult<Retriev
 }
 
 function toSuccessResponse({options, catalogState}: RetrieveCatalogOptionsResponse): CatalogOptions {
-    function toItem(status: ItemStatus): ItemOption {
+    function toItem({status, count}: ItemCount): ItemOption {
         const item = new ItemOption();
+        item.setOutfitCount(count);
         item.setItemId(status.item);
 
         switch (status.type) {
@@ -53,11 +54,17 @@ function toSuccessResponse({options, catalogState}: RetrieveCatalogOptionsRespon
             case "Required":
                 item.setItemStatus(ItemOption.Status.REQUIRED);
                 return item;
+            case "UserExcluded":
+                item.setItemStatus(ItemOption.Status.USER_EXCLUDED);
+                return item;
+            case "Implied":
+                item.setItemStatus(ItemOption.Status.IMPLIED);
+                return item;
         }
     }
 
-    function toFamilyOptions(familyId: string, statuses: ItemStatus[]): FamilyOptions {
-        const items = statuses.map((status: ItemStatus) => toItem(status));
+    function toFamilyOptions(familyId: string, counts: ItemCount[]): FamilyOptions {
+        const items = counts.map((count: ItemCount) => toItem(count));
 
         const familyOptions = new FamilyOptions();
         familyOptions.setFamilyId(familyId);
@@ -66,11 +73,12 @@ function toSuccessResponse({options, catalogState}: RetrieveCatalogOptionsRespon
     }
 
     const familyOptions: FamilyOptions[] =
-        Object.keys(options)
-            .map(familyId => toFamilyOptions(familyId, options[familyId]));
+        Object.keys(options.options)
+            .map(familyId => toFamilyOptions(familyId, options.options[familyId]));
 
     const catalogOptions = new CatalogOptions();
     catalogOptions.setOptionsList(familyOptions);
+    catalogOptions.setTotalOutfits(options.total);
     catalogOptions.setState(SerializedCatalogState(catalogState));
     return catalogOptions;
 }
@@ -175,6 +183,20 @@ function toErrorResponse(error: RetrieveCatalogOptionsError): GrpcServiceError {
                     })
                 ]);
 
+        case "UnsupportedToken":
+            return serviceError(
+                "Outdated catalog, catalog must be re-created",
+                status.FAILED_PRECONDITION,
+                [
+                    preconditionFailureDetail({
+                        violationsList: [{
+                            type: "CatalogToken",
+                            subject: error.catalogId,
+                            description: `Catalog token version ${error.version} is not supported`,
+                        }]
+                    })
+                ]);
+
         case "BadState":
             return serviceError(
                 "Bad Catalog State",
@@ -2,9 +2,9 @@ import {
     CatalogOptionsError,
     CatalogState as FindOptionsParameters,
     CatalogToken,
-    findOptions as findOptionsInner,
-    Item,
-    Options
+    CountedOptions,
+    findCountedOptions,
+    Item
 } from "@ignition/catalogs";
 import {fromLeft as nomadFromLeft} from "@ignition/nomad";
 
@@ -23,9 +23,10 @@ export type RetrieveCatalogOptionsError =
     | { type: "UnknownItems", selections: Item[], exclusions: Item[] }
     | { type: "BadState" }
     | { type: "BadToken", catalogId: string, token: CatalogToken, detail: string }
+    | { type: "UnsupportedToken", catalogId: string, token: CatalogToken, version: number }
 
 export type RetrieveCatalogOptionsResponse = {
-    readonly options: Options;
+    readonly options: CountedOptions;
     readonly catalogState: CatalogState;
 }
 
@@ -62,7 +63,7 @@ function retrieveOptionsFromDatastore(
         .map(entity => ({token: entity.token, selections: oldSelections, exclusions: oldExclusions}) as FindOptionsParameters)
         .chain(params => findOptions(catalogId, params, newSelections, newExclusions))
         .map(([options, {selections, exclusions}]) =>
-            [options, {...previous, selections, exclusions}] as [Options, CatalogState])
+            [options, {...previous, selections, exclusions}] as [CountedOptions, CatalogState])
         .map(([options, catalogState]) => ({options, catalogState}));
 }
 
@@ -71,10 +72,11 @@ function findOptions(
     params: FindOptionsParameters,
     selections: Item[],
     exclusions: Item[]
-): CatalogsResult<RetrieveCatalogOptionsError, [Options, FindOptionsParameters]> {
+): CatalogsResult<RetrieveCatalogOptionsError, [CountedOptions, FindOptionsParameters]> {
     const errHandler = (err: CatalogOptionsError): RetrieveCatalogOptionsError => {
         switch (err.type) {
             case "BadToken":
+            case "UnsupportedToken":
                 return {...err, catalogId: catalogId};
             case "UnknownSelections":
             case "UnknownExclusions":
@@ -84,7 +86,7 @@ function findOptions(
         }
     };
 
-    return findOptionsInner(params, selections, exclusions)
+    return findCountedOptions(params, selections, exclusions)
         .mapLeft(errHandler)
         .toNomadRTE();
 }
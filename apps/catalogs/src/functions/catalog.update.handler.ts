@@ -1,5 +1,5 @@
 import {nomadRTE} from "@ignition/nomad";
-import {CatalogExclusionRule, CatalogFamilies, CatalogInclusionRule, ItemStatus} from "@ignition/catalogs";
+import {CatalogExclusionRule, CatalogFamilies, CatalogInclusionRule, Item, ItemCount} from "@ignition/catalogs";
 
 import {CatalogOptions, FamilyOptions, ItemOption, UpdateCatalogRequest} from "../../generated/catalogs_pb";
 import {status} from "grpc";
@@ -36,12 +36,12 @@ function fromRequest(req: UpdateCatalogRequest): CatalogsResult<UpdateCatalogErr
         .reduce((acc, rule) => ([...acc, {
             conditions: rule.getConditionsList(),
             exclusions: rule.getExclusionsList()
-        }]), [] as CatalogExclusionRule[]);
+        }]), [] as CatalogExclusionRule<Item>[]);
     const inclusions = req.getInclusionsList()
         .reduce((acc, rule) => ([...acc, {
             conditions: rule.getConditionsList(),
             inclusions: rule.getInclusionsList()
-        }]), [] as CatalogInclusionRule[]);
+        }]), [] as CatalogInclusionRule<Item>[]);
 
     const assembly = {
         families: families,
@@ -52,8 +52,9 @@ function fromRequest(req: UpdateCatalogRequest): CatalogsResult<UpdateCatalogErr
 }
 
 function toSuccessResponse({projectId, catalogId, options}: UpdateCatalogResponse): CatalogOptions {
-    function toItem(status: ItemStatus): ItemOption {
+    function toItem({status, count}: ItemCount): ItemOption {
         const item = new ItemOption();
+        item.setOutfitCount(count);
         switch (status.type) {
             case "Available":
                 item.setItemId(status.item);
@@ -71,11 +72,19 @@ function toSuccessResponse({projectId, catalogId, options}: UpdateCatalogRespons
                 item.setItemId(status.item);
                 item.setItemStatus(ItemOption.Status.REQUIRED);
                 return item;
+            case "UserExcluded":
+                item.setItemId(status.item);
+                item.setItemStatus(ItemOption.Status.USER_EXCLUDED);
+                return item;
+            case "Implied":
+                item.setItemId(status.item);
+                item.setItemStatus(ItemOption.Status.IMPLIED);
+                return item;
         }
     }
 
-    function toFamilyOptions(familyId: string, statuses: ItemStatus[]): FamilyOptions {
-        const items = statuses.map((status: ItemStatus) => toItem(status));
+    function toFamilyOptions(familyId: string, counts: ItemCount[]): FamilyOptions {
+        const items = counts.map((count: ItemCount) => toItem(count));
 
         const familyOptions = new FamilyOptions();
         familyOptions.setFamilyId(familyId);
@@ -84,11 +93,12 @@ function toSuccessResponse({projectId, catalogId, options}: UpdateCatalogRespons
     }
 
     const familyOptions: FamilyOptions[] =
-        Object.keys(options)
-            .map(familyId => toFamilyOptions(familyId, options[familyId]));
+        Object.keys(options.options)
+            .map(familyId => toFamilyOptions(familyId, options.options[familyId]));
 
     const catalogOptions = new CatalogOptions();
     catalogOptions.setOptionsList(familyOptions);
+    catalogOptions.setTotalOutfits(options.total);
     catalogOptions.setState(defaultCatalogState(projectId, catalogId));
     return catalogOptions;
 }
@@ -202,6 +212,8 @@ function toErrorResponseDetails(error: UpdateCatalogError): GrpcServiceErrorDeta
 
         case "BadToken":
             return [];
+        case "UnsupportedToken":
+            return [];
         case "BadState":
             return [];
     }
@@ -286,6 +298,7 @@ function toErrorResponse(error: UpdateCatalogError): GrpcServiceError {
                 toErrorResponseDetails(error));
 
         case "BadToken":
+        case "UnsupportedToken":
             return serviceError(
                 "Catalog was not created correctly",
                 status.INTERNAL,
@@ -7,8 +7,8 @@ import {
     buildCatalog,
     CatalogBuildError,
     CatalogOptionsError,
-    findOptions as findOptionsInner,
-    Options,
+    CountedOptions,
+    findCountedOptions,
 } from "@ignition/catalogs";
 
 import {tryCatch} from "fp-ts/lib/TaskEither";
@@ -31,7 +31,7 @@ export type UpdateCatalogError =
 export type UpdateCatalogResponse = {
     readonly projectId: string;
     readonly catalogId: string;
-    readonly options: Options;
+    readonly options: CountedOptions;
 }
 
 type ProjectId = string;
@@ -53,7 +53,7 @@ export function updateCatalog(
                 .chain(saveCatalogEntity)
                 .map(() => catalogState)
         )
-        .chain(catalogState => findOptionsInner(catalogState).toNomadRTE<Datastore>())
+        .chain(catalogState => findCountedOptions(catalogState).toNomadRTE<Datastore>())
         .map(([options, _]) => ({projectId: projectId, catalogId: catalogId, options: options}));
 }
 
@@ -74,19 +74,22 @@ test("createCatalog returns 'created' when catalog is properly formed", async (t
 
     const expected = {
         state: defaultCatalogState(projectId, catalogId),
+        totalOutfits: 4,
+        droppedSelectionsList: [],
+        droppedExclusionsList: [],
         optionsList: [
             {
                 familyId: "pants",
                 optionsList: [
-                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:jeans"},
-                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:slacks"},
+                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:jeans", "outfitCount": 2},
+                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:slacks", "outfitCount": 2},
                 ]
             },
             {
                 familyId: "shirts",
                 optionsList: [
-                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:black"},
-                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:red"},
+                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:black", "outfitCount": 2},
+                    {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:red", "outfitCount": 2},
                 ]
             }
         ]
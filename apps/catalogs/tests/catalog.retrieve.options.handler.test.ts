@@ -26,34 +26,38 @@ const inputFamilies = {
     "pants": ["pants:jeans", "pants:slacks"]
 };
 
-const pants_allAvailable = {
-    familyId: "pants",
-    optionsList: [
-        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:jeans"},
-        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:slacks"},
-    ]
-};
+function pants_allAvailable(outfitCount: number) {
+    return {
+        familyId: "pants",
+        optionsList: [
+            {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:jeans", "outfitCount": outfitCount},
+            {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "pants:slacks", "outfitCount": outfitCount},
+        ]
+    };
+}
 const shirts_allAvailable = {
     familyId: "shirts",
     optionsList: [
-        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:black"},
-        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:red"},
+        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:black", "outfitCount": 2},
+        {"itemStatus": ItemOption.Status.AVAILABLE, "itemId": "shirts:red", "outfitCount": 2},
     ]
 };
 const pants_slacksSelected = {
     familyId: "pants",
     optionsList: [
-        {"itemStatus": ItemOption.Status.SELECTED, "itemId": "pants:slacks"},
-        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:jeans"},
-    ]
-};
-const shirts_redSelected = {
-    familyId: "shirts",
-    optionsList: [
-        {"itemStatus": ItemOption.Status.SELECTED, "itemId": "shirts:red"},
-        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:black"},
+        {"itemStatus": ItemOption.Status.SELECTED, "itemId": "pants:slacks", "outfitCount": 1},
+        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:jeans", "outfitCount": 0},
     ]
 };
+function shirts_redSelected(outfitCount: number) {
+    return {
+        familyId: "shirts",
+        optionsList: [
+            {"itemStatus": ItemOption.Status.SELECTED, "itemId": "shirts:red", "outfitCount": outfitCount},
+            {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:black", "outfitCount": 0},
+        ]
+    };
+}
 
 const scenarios: Scenario[] = [
     {
@@ -63,8 +67,11 @@ const scenarios: Scenario[] = [
         exclusions: [],
         expected: {
             state: defaultCatalogState(projectId, "catalog-1"),
+            totalOutfits: 4,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
-                pants_allAvailable,
+                pants_allAvailable(2),
                 shirts_allAvailable
             ]
         }
@@ -76,8 +83,11 @@ const scenarios: Scenario[] = [
         exclusions: [],
         expected: {
             state: defaultCatalogState(projectId, "catalog-2"),
+            totalOutfits: 4,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
-                pants_allAvailable,
+                pants_allAvailable(2),
                 shirts_allAvailable
             ]
         }
@@ -94,9 +104,12 @@ const scenarios: Scenario[] = [
                 selections: ["shirts:red"],
                 exclusions: []
             }),
+            totalOutfits: 2,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
-                pants_allAvailable,
-                shirts_redSelected
+                pants_allAvailable(1),
+                shirts_redSelected(2)
             ]
         }
     },
@@ -112,9 +125,12 @@ const scenarios: Scenario[] = [
                 selections: ["pants:slacks", "shirts:red"],
                 exclusions: []
             }),
+            totalOutfits: 1,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
                 pants_slacksSelected,
-                shirts_redSelected
+                shirts_redSelected(1)
             ]
         }
     },
@@ -130,9 +146,12 @@ const scenarios: Scenario[] = [
                 selections: ["pants:slacks", "shirts:red"],
                 exclusions: []
             }),
+            totalOutfits: 1,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
                 pants_slacksSelected,
-                shirts_redSelected
+                shirts_redSelected(1)
             ]
         }
     },
@@ -148,19 +167,22 @@ const scenarios: Scenario[] = [
                 selections: ["shirts:black", "shirts:red"],
                 exclusions: []
             }),
+            totalOutfits: 0,
+            droppedSelectionsList: [],
+            droppedExclusionsList: [],
             optionsList: [
                 {
                     familyId: "pants",
                     optionsList: [
-                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:jeans"},
-                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:slacks"},
+                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:jeans", "outfitCount": 0},
+                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "pants:slacks", "outfitCount": 0},
                     ]
                 },
                 {
                     familyId: "shirts",
                     optionsList: [
-                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:red"},
-                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:black"},
+                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:red", "outfitCount": 0},
+                        {"itemStatus": ItemOption.Status.EXCLUDED, "itemId": "shirts:black", "outfitCount": 0},
                     ]
                 }
             ]
@@ -240,9 +262,12 @@ test("retrieveCatalogOptions returns catalog options, with updated catalogState,
     });
     const expected = {
         state: expectedState,
+        totalOutfits: 2,
+        droppedSelectionsList: [],
+        droppedExclusionsList: [],
         optionsList: [
-            pants_allAvailable,
-            shirts_redSelected
+            pants_allAvailable(1),
+            shirts_redSelected(2)
         ]
     };
 
@@ -289,9 +314,12 @@ test("retrieveCatalogOptions returns catalog options, with updated catalogState,
     });
     const expected = {
         state: expectedState,
+        totalOutfits: 1,
+        droppedSelectionsList: [],
+        droppedExclusionsList: [],
         optionsList: [
             pants_slacksSelected,
-            shirts_redSelected
+            shirts_redSelected(1)
         ]
     };
 
@@ -331,8 +359,11 @@ test("retrieveCatalogOptions returns default, when request contains bad token",
 
     const expected = {
         state: defaultCatalogState(projectId, catalogId),
+        totalOutfits: 4,
+        droppedSelectionsList: [],
+        droppedExclusionsList: [],
         optionsList: [
-            pants_allAvailable,
+            pants_allAvailable(2),
             shirts_allAvailable
         ]
     };
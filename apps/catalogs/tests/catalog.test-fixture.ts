@@ -23,8 +23,8 @@ export async function buildTestCatalogEntity(
     catalogId: string,
     created: Date,
     families: CatalogFamilies,
-    exclusions: CatalogExclusionRule[] = [],
-    inclusions: CatalogInclusionRule[] = []
+    exclusions: CatalogExclusionRule<Item>[] = [],
+    inclusions: CatalogInclusionRule<Item>[] = []
 ): Promise<CatalogEntity> {
     const [catalogOrError] = await buildCatalog(families, exclusions, inclusions)
         .map(state => state.token)
@@ -43,8 +43,8 @@ export async function buildTestCatalogEntity(
 export async function buildTestCatalogToken(
     families: CatalogFamilies,
     selections: Item[] = [],
-    exclusions: CatalogExclusionRule[] = [],
-    inclusions: CatalogInclusionRule[] = []
+    exclusions: CatalogExclusionRule<Item>[] = [],
+    inclusions: CatalogInclusionRule<Item>[] = []
 ): Promise<CatalogToken> {
     const catalogState = await buildCatalog(families, exclusions, inclusions)
         .fold(() => EMPTY_CATALOG_STATE, res => res)
@@ -0,0 +1,510 @@
+extern crate chrono;
+extern crate ignition_catalogs_wasm;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+extern crate serde_yaml;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use ignition_catalogs_wasm::catalog::CatalogState;
+use ignition_catalogs_wasm::catalog_builder::{build_catalog, build_catalog_with_report, diff_assemblies, dry_run, AssemblyEdit, CatalogAssembly};
+use ignition_catalogs_wasm::errors::CatalogsError;
+use ignition_catalogs_wasm::storage::{CatalogRecord, CatalogStore, Expected, FileCatalogStore};
+use ignition_catalogs_wasm::types::Item;
+
+const USAGE: &str = "\
+Usage:
+    ignition-catalog build <assembly>
+    ignition-catalog report <assembly>
+    ignition-catalog options <source> [--select <items>] [--exclude <items>]
+    ignition-catalog outfits <source> [--select <items>] [--exclude <items>]
+    ignition-catalog count <source> [--select <items>] [--exclude <items>]
+    ignition-catalog put <store> <project>/<catalog> <assembly>
+    ignition-catalog catalogs <store> <project>
+    ignition-catalog history <store> <project>/<catalog>
+    ignition-catalog rollback <store> <project>/<catalog> <version>
+    ignition-catalog diff <assembly> <assembly>
+    ignition-catalog diff <store> <project>/<catalog> <version> <version>
+    ignition-catalog impact <assembly> <edit>
+
+An <assembly> is a CatalogAssembly and an <edit> an AssemblyEdit, in JSON (.json) or YAML (.yaml, .yml).
+A <source> is one of:
+    --assembly <file>   build the catalog from an assembly
+    --state <file>      a catalog state as printed by `build`
+    --token <token>     a catalog token
+    --store <dir> --catalog <project>/<catalog>
+                        a catalog kept in a store
+A <store> is a directory of catalogs, as written by `put`.
+<items> is a comma separated list, and may be repeated.";
+
+#[derive(Debug, Eq, PartialEq)]
+enum Source {
+    Assembly(PathBuf),
+    State(PathBuf),
+    Token(String),
+    Stored { store: PathBuf, project_id: String, catalog_id: String },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct Query {
+    source: Source,
+    selections: Vec<Item>,
+    exclusions: Vec<Item>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum DiffSource {
+    Assemblies(PathBuf, PathBuf),
+    Versions { store: PathBuf, project_id: String, catalog_id: String, before: u64, after: u64 },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Command {
+    Build(PathBuf),
+    Report(PathBuf),
+    Options(Query),
+    Outfits(Query),
+    Count(Query),
+    Put { store: PathBuf, project_id: String, catalog_id: String, assembly: PathBuf },
+    Catalogs { store: PathBuf, project_id: String },
+    History { store: PathBuf, project_id: String, catalog_id: String },
+    Rollback { store: PathBuf, project_id: String, catalog_id: String, version: u64 },
+    Diff(DiffSource),
+    Impact { assembly: PathBuf, edit: PathBuf },
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(message) = run(command) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = args.split_first()
+        .ok_or_else(|| String::from("missing command"))?;
+
+    match command.as_str() {
+        "build" => match rest {
+            [assembly] => Ok(Command::Build(PathBuf::from(assembly))),
+            _ => Err(String::from("build takes exactly one assembly file")),
+        },
+        "options" => parse_query(rest).map(Command::Options),
+        "outfits" => parse_query(rest).map(Command::Outfits),
+        "count" => parse_query(rest).map(Command::Count),
+        "put" => match rest {
+            [store, catalog, assembly] => {
+                let (project_id, catalog_id) = split_catalog(catalog)?;
+                Ok(Command::Put { store: PathBuf::from(store), project_id, catalog_id, assembly: PathBuf::from(assembly) })
+            }
+            _ => Err(String::from("put takes a store, a catalog and an assembly file")),
+        },
+        "catalogs" => match rest {
+            [store, project_id] => Ok(Command::Catalogs { store: PathBuf::from(store), project_id: project_id.clone() }),
+            _ => Err(String::from("catalogs takes a store and a project")),
+        },
+        "history" => match rest {
+            [store, catalog] => {
+                let (project_id, catalog_id) = split_catalog(catalog)?;
+                Ok(Command::History { store: PathBuf::from(store), project_id, catalog_id })
+            }
+            _ => Err(String::from("history takes a store and a catalog")),
+        },
+        "rollback" => match rest {
+            [store, catalog, version] => {
+                let (project_id, catalog_id) = split_catalog(catalog)?;
+                let version = parse_version(version)?;
+                Ok(Command::Rollback { store: PathBuf::from(store), project_id, catalog_id, version })
+            }
+            _ => Err(String::from("rollback takes a store, a catalog and a version")),
+        },
+        "diff" => match rest {
+            [before, after] => Ok(Command::Diff(DiffSource::Assemblies(PathBuf::from(before), PathBuf::from(after)))),
+            [store, catalog, before, after] => {
+                let (project_id, catalog_id) = split_catalog(catalog)?;
+                Ok(Command::Diff(DiffSource::Versions {
+                    store: PathBuf::from(store),
+                    project_id,
+                    catalog_id,
+                    before: parse_version(before)?,
+                    after: parse_version(after)?,
+                }))
+            }
+            _ => Err(String::from("diff takes two assembly files, or a store, a catalog and two versions")),
+        },
+        "report" => match rest {
+            [assembly] => Ok(Command::Report(PathBuf::from(assembly))),
+            _ => Err(String::from("report takes exactly one assembly file")),
+        },
+        "impact" => match rest {
+            [assembly, edit] => Ok(Command::Impact { assembly: PathBuf::from(assembly), edit: PathBuf::from(edit) }),
+            _ => Err(String::from("impact takes an assembly file and an edit file")),
+        },
+        command => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+fn parse_query(args: &[String]) -> Result<Query, String> {
+    let mut source = None;
+    let mut store = None;
+    let mut selections = vec![];
+    let mut exclusions = vec![];
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next()
+            .ok_or_else(|| format!("missing value for '{}'", flag))?;
+
+        let next_source = match flag.as_str() {
+            "--assembly" => Some(Source::Assembly(PathBuf::from(value))),
+            "--state" => Some(Source::State(PathBuf::from(value))),
+            "--token" => Some(Source::Token(value.clone())),
+            "--catalog" => {
+                let (project_id, catalog_id) = split_catalog(value)?;
+                Some(Source::Stored { store: PathBuf::new(), project_id, catalog_id })
+            }
+            "--store" => {
+                store = Some(PathBuf::from(value));
+                None
+            }
+            "--select" => {
+                selections.extend(split_items(value));
+                None
+            }
+            "--exclude" => {
+                exclusions.extend(split_items(value));
+                None
+            }
+            flag => return Err(format!("unknown flag '{}'", flag)),
+        };
+
+        if next_source.is_some() {
+            if source.is_some() {
+                return Err(String::from("only one of --assembly, --state, --token or --catalog may be given"));
+            }
+            source = next_source;
+        }
+    }
+
+    let source = match (source, store) {
+        (Some(Source::Stored { project_id, catalog_id, .. }), Some(store)) => Source::Stored { store, project_id, catalog_id },
+        (Some(Source::Stored { .. }), None) => return Err(String::from("--catalog requires --store")),
+        (Some(_), Some(_)) => return Err(String::from("--store is only used with --catalog")),
+        (Some(source), None) => source,
+        (None, _) => return Err(String::from("one of --assembly, --state, --token or --catalog is required")),
+    };
+    Ok(Query { source, selections, exclusions })
+}
+
+fn split_catalog(catalog: &str) -> Result<(String, String), String> {
+    match catalog.split('/').collect::<Vec<_>>().as_slice() {
+        [project_id, catalog_id] if !project_id.is_empty() && !catalog_id.is_empty() =>
+            Ok((project_id.to_string(), catalog_id.to_string())),
+        _ => Err(format!("'{}' is not of the form <project>/<catalog>", catalog)),
+    }
+}
+
+fn parse_version(version: &str) -> Result<u64, String> {
+    version.parse()
+        .map_err(|_| format!("'{}' is not a version", version))
+}
+
+fn split_items(items: &str) -> Vec<Item> {
+    items.split(',')
+        .map(|item| Item::from(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Build(path) => {
+            let state = build(&path)?;
+            print(&state)
+        }
+        Command::Options(query) => {
+            let (options, _) = load(&query.source)?
+                .options(&query.selections, &query.exclusions)
+                .map_err(|err| describe(&err))?;
+            print(&options)
+        }
+        Command::Outfits(query) => {
+            let (outfits, _) = load(&query.source)?
+                .combinations(&query.selections, &query.exclusions)
+                .map_err(|err| describe(&err))?;
+            print(&outfits)
+        }
+        Command::Count(query) => {
+            let (count, _) = load(&query.source)?
+                .count(&query.selections, &query.exclusions)
+                .map_err(|err| describe(&err))?;
+            print(&count)
+        }
+        Command::Put { store, project_id, catalog_id, assembly } => {
+            let assembly = read_assembly(&assembly)?;
+            let state = build_catalog(assembly.clone())
+                .map(CatalogState::from_catalog)
+                .map_err(|err| describe(&err))?;
+
+            let record = CatalogRecord::new(&project_id, &catalog_id, Utc::now().to_rfc3339(), assembly, state);
+            let record = FileCatalogStore::new(store)
+                .put(record, Expected::Any)
+                .map_err(|err| describe(&err))?;
+            print(&summary(&record))
+        }
+        Command::Catalogs { store, project_id } => {
+            let records = FileCatalogStore::new(store)
+                .list(&project_id)
+                .map_err(|err| describe(&err))?;
+            print(&records.iter().map(summary).collect::<Vec<_>>())
+        }
+        Command::History { store, project_id, catalog_id } => {
+            let records = FileCatalogStore::new(store)
+                .history(&project_id, &catalog_id)
+                .map_err(|err| describe(&err))?;
+            print(&records.iter().map(summary).collect::<Vec<_>>())
+        }
+        Command::Rollback { store, project_id, catalog_id, version } => {
+            let record = FileCatalogStore::new(store)
+                .rollback(&project_id, &catalog_id, version, Expected::Any)
+                .map_err(|err| describe(&err))?;
+            print(&summary(&record))
+        }
+        Command::Diff(source) => {
+            let (before, after) = match source {
+                DiffSource::Assemblies(before, after) => (read_assembly(&before)?, read_assembly(&after)?),
+                DiffSource::Versions { store, project_id, catalog_id, before, after } => {
+                    let store = FileCatalogStore::new(store);
+                    let before = store.get_version(&project_id, &catalog_id, before)
+                        .map_err(|err| describe(&err))?;
+                    let after = store.get_version(&project_id, &catalog_id, after)
+                        .map_err(|err| describe(&err))?;
+
+                    (before.assembly().clone(), after.assembly().clone())
+                }
+            };
+
+            let diff = diff_assemblies(&before, &after)
+                .map_err(|err| describe(&err))?;
+            print(&diff)
+        }
+        Command::Report(path) => {
+            let (_, report) = build_catalog_with_report(read_assembly(&path)?)
+                .map_err(|err| describe(&err))?;
+            print(&report)
+        }
+        Command::Impact { assembly, edit } => {
+            let edit: AssemblyEdit = read_document(&edit)?;
+            let impact = dry_run(&read_assembly(&assembly)?, &edit)
+                .map_err(|err| describe(&err))?;
+            print(&impact)
+        }
+    }
+}
+
+fn read_assembly(path: &Path) -> Result<CatalogAssembly, String> {
+    read_document(path)
+}
+
+fn read_document<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = read(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+            .map_err(|err| format!("{}: {}", path.display(), err)),
+        _ => serde_json::from_str(&contents)
+            .map_err(|err| format!("{}: {}", path.display(), err)),
+    }
+}
+
+fn build(path: &Path) -> Result<CatalogState, String> {
+    build_catalog(read_assembly(path)?)
+        .map(CatalogState::from_catalog)
+        .map_err(|err| describe(&err))
+}
+
+fn load(source: &Source) -> Result<CatalogState, String> {
+    match source {
+        Source::Assembly(path) => build(path),
+        Source::State(path) => serde_json::from_str(&read(path)?)
+            .map_err(|err| format!("{}: {}", path.display(), err)),
+        Source::Token(token) => serde_json::from_value(json!({ "token": token, "selections": [], "exclusions": [] }))
+            .map_err(|err| err.to_string()),
+        Source::Stored { store, project_id, catalog_id } => FileCatalogStore::new(store.clone())
+            .get(project_id, catalog_id)
+            .map(|record| record.state().clone())
+            .map_err(|err| describe(&err)),
+    }
+}
+
+fn summary(record: &CatalogRecord) -> serde_json::Value {
+    json!({
+        "project_id": record.project_id(),
+        "catalog_id": record.catalog_id(),
+        "version": record.version(),
+        "hash": record.hash(),
+        "created": record.created(),
+    })
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn print<T: Serialize>(value: &T) -> Result<(), String> {
+    println!("{}", to_json(value));
+    Ok(())
+}
+
+fn describe<E: CatalogsError>(err: &E) -> String {
+    format!("{}: {}", err.code(), err)
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_args, Command, DiffSource, Query, Source};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| String::from(arg)).collect()
+    }
+
+    #[test]
+    fn build_takes_an_assembly_file() {
+        assert_eq!(
+            Ok(Command::Build(PathBuf::from("catalog.yaml"))),
+            parse_args(&args(&["build", "catalog.yaml"]))
+        );
+    }
+
+    #[test]
+    fn queries_collect_selections_and_exclusions() {
+        assert_eq!(
+            Ok(Command::Options(Query {
+                source: Source::Token(String::from("abc")),
+                selections: vec![String::from("shirts:red"), String::from("pants:jeans")],
+                exclusions: vec![String::from("shoes:loafers")],
+            })),
+            parse_args(&args(&["options", "--token", "abc", "--select", "shirts:red, pants:jeans", "--exclude", "shoes:loafers"]))
+        );
+    }
+
+    #[test]
+    fn queries_require_exactly_one_source() {
+        assert!(parse_args(&args(&["count", "--select", "shirts:red"])).is_err());
+        assert!(parse_args(&args(&["count", "--state", "state.json", "--token", "abc"])).is_err());
+    }
+
+    #[test]
+    fn queries_can_read_stored_catalogs() {
+        assert_eq!(
+            Ok(Command::Count(Query {
+                source: Source::Stored {
+                    store: PathBuf::from("catalogs"),
+                    project_id: String::from("project"),
+                    catalog_id: String::from("summer"),
+                },
+                selections: vec![],
+                exclusions: vec![],
+            })),
+            parse_args(&args(&["count", "--catalog", "project/summer", "--store", "catalogs"]))
+        );
+        assert!(parse_args(&args(&["count", "--catalog", "project/summer"])).is_err());
+        assert!(parse_args(&args(&["count", "--token", "abc", "--store", "catalogs"])).is_err());
+        assert!(parse_args(&args(&["count", "--catalog", "summer", "--store", "catalogs"])).is_err());
+    }
+
+    #[test]
+    fn put_takes_a_store_a_catalog_and_an_assembly() {
+        assert_eq!(
+            Ok(Command::Put {
+                store: PathBuf::from("catalogs"),
+                project_id: String::from("project"),
+                catalog_id: String::from("summer"),
+                assembly: PathBuf::from("summer.yaml"),
+            }),
+            parse_args(&args(&["put", "catalogs", "project/summer", "summer.yaml"]))
+        );
+    }
+
+    #[test]
+    fn rollback_takes_a_version() {
+        assert_eq!(
+            Ok(Command::Rollback {
+                store: PathBuf::from("catalogs"),
+                project_id: String::from("project"),
+                catalog_id: String::from("summer"),
+                version: 2,
+            }),
+            parse_args(&args(&["rollback", "catalogs", "project/summer", "2"]))
+        );
+        assert!(parse_args(&args(&["rollback", "catalogs", "project/summer", "latest"])).is_err());
+    }
+
+    #[test]
+    fn diff_compares_assemblies_or_stored_versions() {
+        assert_eq!(
+            Ok(Command::Diff(DiffSource::Assemblies(PathBuf::from("before.yaml"), PathBuf::from("after.yaml")))),
+            parse_args(&args(&["diff", "before.yaml", "after.yaml"]))
+        );
+        assert_eq!(
+            Ok(Command::Diff(DiffSource::Versions {
+                store: PathBuf::from("catalogs"),
+                project_id: String::from("project"),
+                catalog_id: String::from("summer"),
+                before: 1,
+                after: 3,
+            })),
+            parse_args(&args(&["diff", "catalogs", "project/summer", "1", "3"]))
+        );
+    }
+
+    #[test]
+    fn report_takes_an_assembly() {
+        assert_eq!(
+            Ok(Command::Report(PathBuf::from("summer.yaml"))),
+            parse_args(&args(&["report", "summer.yaml"]))
+        );
+        assert!(parse_args(&args(&["report"])).is_err());
+    }
+
+    #[test]
+    fn impact_takes_an_assembly_and_an_edit() {
+        assert_eq!(
+            Ok(Command::Impact { assembly: PathBuf::from("summer.yaml"), edit: PathBuf::from("edit.json") }),
+            parse_args(&args(&["impact", "summer.yaml", "edit.json"]))
+        );
+        assert!(parse_args(&args(&["impact", "summer.yaml"])).is_err());
+    }
+
+    #[test]
+    fn unknown_commands_are_rejected() {
+        assert_eq!(
+            Err(String::from("unknown command 'list'")),
+            parse_args(&args(&["list"]))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use types::{Family, Item};
+
+use super::Catalog;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum CompletionMode {
+    First,
+    Random { seed: u64 },
+}
+
+impl Catalog {
+    pub fn complete(self, selections: &[Item], exclusions: &[Item], mode: CompletionMode) -> Option<Vec<Item>> {
+        let mut catalog = self.restrict(selections, exclusions);
+        let mut rng = match mode {
+            CompletionMode::First => None,
+            CompletionMode::Random { seed } => Some(SplitMix64::new(seed)),
+        };
+
+        let mut outfit: Vec<Item> = vec![];
+        loop {
+            let total = catalog.combinations.len();
+            if total == 0 {
+                return None;
+            }
+
+            let open = catalog.combinations.occurrences()
+                .into_iter()
+                .filter(|(item, count)| *count > 0 && !outfit.contains(item))
+                .collect::<Vec<_>>();
+            if open.is_empty() {
+                break;
+            }
+
+            let pick = match rng {
+                None => open.into_iter().map(|(item, _)| item).min().map(Pick::Item),
+                Some(ref mut rng) => pick_weighted(&catalog.items, open, total, rng),
+            };
+
+            catalog = match pick {
+                Some(Pick::Item(item)) => {
+                    let catalog = catalog.restrict(&[item.clone()], &[]);
+                    outfit.push(item);
+                    catalog
+                }
+                Some(Pick::Without(items)) => catalog.restrict(&[], &items),
+                None => break,
+            };
+        }
+
+        outfit.sort();
+        Some(outfit)
+    }
+}
+
+/// An item picked for the outfit, or a family left out of it along with the family's items.
+enum Pick {
+    Item(Item),
+    Without(Vec<Item>),
+}
+
+/// Picks an item from the first open family, weighted by how many outfits contain it,
+/// or leaves the family out, weighted by how many outfits have none of its items.
+/// Every outfit contains at most one item per family, so repeating this produces a uniformly random outfit.
+fn pick_weighted(items: &HashMap<Item, Family>, open: Vec<(Item, usize)>, total: usize, rng: &mut SplitMix64) -> Option<Pick> {
+    let family = open.iter()
+        .map(|(item, _)| &items[item])
+        .min()?
+        .clone();
+
+    let mut target = rng.below(total as u64) as usize;
+    for (item, count) in open.into_iter().filter(|(item, _)| items[item] == family).sorted() {
+        if target < count {
+            return Some(Pick::Item(item));
+        }
+        target -= count;
+    }
+
+    let family_items = items.iter()
+        .filter(|&(_, item_family)| *item_family == family)
+        .map(|(item, _)| item.clone())
+        .collect();
+
+    Some(Pick::Without(family_items))
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures;
+    use types::Item;
+
+    use super::{Catalog, CompletionMode};
+
+    fn catalog() -> Catalog {
+        fixtures::catalog(&[
+            &["pants:jeans", "shirts:red"],
+            &["pants:slacks", "shirts:blue"],
+            &["pants:slacks", "shirts:red"],
+        ])
+    }
+
+    #[test]
+    fn first_completion_is_lexicographically_first_outfit() {
+        let outfit = catalog().complete(&[], &[], CompletionMode::First);
+
+        assert_eq!(
+            Some(vec![Item::from("pants:jeans"), Item::from("shirts:red")]),
+            outfit
+        );
+    }
+
+    #[test]
+    fn first_completion_extends_selections() {
+        let outfit = catalog().complete(&[Item::from("shirts:blue")], &[], CompletionMode::First);
+
+        assert_eq!(
+            Some(vec![Item::from("pants:slacks"), Item::from("shirts:blue")]),
+            outfit
+        );
+    }
+
+    #[test]
+    fn first_completion_respects_exclusions() {
+        let outfit = catalog().complete(&[], &[Item::from("pants:jeans")], CompletionMode::First);
+
+        assert_eq!(
+            Some(vec![Item::from("pants:slacks"), Item::from("shirts:blue")]),
+            outfit
+        );
+    }
+
+    #[test]
+    fn completion_is_none_when_no_outfit_remains() {
+        let outfit = catalog().complete(&[Item::from("pants:jeans"), Item::from("shirts:blue")], &[], CompletionMode::First);
+
+        assert_eq!(None, outfit);
+    }
+
+    #[test]
+    fn random_completion_is_a_valid_outfit() {
+        let catalog = catalog();
+        let outfits = catalog.combinations()
+            .into_iter()
+            .map(|mut outfit| {
+                outfit.sort();
+                outfit
+            })
+            .collect::<Vec<_>>();
+
+        for seed in 0..20 {
+            let outfit = catalog.clone().complete(&[], &[], CompletionMode::Random { seed })
+                .expect("expected an outfit");
+
+            assert!(outfits.contains(&outfit), "{:?} is not a valid outfit", outfit);
+        }
+    }
+
+    #[test]
+    fn both_modes_complete_outfits_missing_a_family() {
+        let catalog = fixtures::catalog(&[
+            &["belts:black", "pants:jeans"],
+            &["pants:slacks"],
+        ]);
+        let outfits = fixtures::sorted_outfits(catalog.combinations());
+
+        assert_eq!(
+            Some(vec![Item::from("belts:black"), Item::from("pants:jeans")]),
+            catalog.clone().complete(&[], &[], CompletionMode::First)
+        );
+
+        let completed = (0..20)
+            .map(|seed| catalog.clone().complete(&[], &[], CompletionMode::Random { seed }).expect("expected an outfit"))
+            .collect::<Vec<_>>();
+
+        assert!(completed.iter().all(|outfit| outfits.contains(outfit)), "{:?} are not all valid outfits", completed);
+        assert!(completed.contains(&vec![Item::from("pants:slacks")]));
+        assert!(completed.contains(&vec![Item::from("belts:black"), Item::from("pants:jeans")]));
+    }
+
+    #[test]
+    fn random_completion_is_deterministic_for_a_seed() {
+        let catalog = catalog();
+
+        assert_eq!(
+            catalog.clone().complete(&[], &[], CompletionMode::Random { seed: 42 }),
+            catalog.complete(&[], &[], CompletionMode::Random { seed: 42 })
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use types::{Family, Item, ItemStatus};
+
+use super::Catalog;
+
+/// The selections that would have to be dropped to pick an item.
+/// `conflicts` is empty when picking the item is always safe, and `None` when no outfit contains it under the exclusions.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ItemConflicts {
+    status: ItemStatus<Item>,
+    conflicts: Option<Vec<Item>>,
+}
+
+pub type ConflictOptions = BTreeMap<Family, Vec<ItemConflicts>>;
+
+impl Catalog {
+    /// Each item's conflicts come from a search of the outfits containing it for the one keeping the most selections.
+    pub fn conflict_options(mut self, selections: &[Item], exclusions: &[Item]) -> ConflictOptions {
+        let required = self.required_items();
+        let forest = self.take_restricted(&[], exclusions);
+        let kept = |item: &Item| if selections.contains(item) { -1.0 } else { 0.0 };
+
+        self.restricted_item_statuses(&forest.clone().subset_all(selections), &required, selections, exclusions)
+            .into_iter()
+            .map(|(family, (status, _))| {
+                let outfits = forest.clone().subset_all(&[status.item().clone()]);
+                let conflicts = self.cheapest(outfits, kept, 1)
+                    .into_iter()
+                    .next()
+                    .map(|(outfit, _)| selections.iter()
+                        .filter(|selection| !outfit.contains(selection))
+                        .cloned()
+                        .sorted()
+                        .collect());
+
+                (family, ItemConflicts { status, conflicts })
+            })
+            .into_group_map::<Family, ItemConflicts>()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures;
+    use types::{Item, ItemStatus};
+
+    use super::{Catalog, ItemConflicts};
+
+    fn catalog() -> Catalog {
+        fixtures::catalog(&[
+            &["pants:jeans", "shirts:blue", "shoes:sneakers"],
+            &["pants:jeans", "shirts:red", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+        ])
+    }
+
+    fn conflicts(status: ItemStatus<Item>, conflicts: &[&str]) -> ItemConflicts {
+        ItemConflicts { status, conflicts: Some(conflicts.iter().map(|&item| Item::from(item)).collect()) }
+    }
+
+    fn sorted(mut items: Vec<ItemConflicts>) -> Vec<ItemConflicts> {
+        items.sort_by(|a, b| a.status.item().cmp(b.status.item()));
+        items
+    }
+
+    #[test]
+    fn available_items_are_safe_to_pick() {
+        let options = catalog().conflict_options(&[Item::from("shirts:blue")], &[]);
+
+        assert_eq!(
+            vec![
+                conflicts(ItemStatus::Available(Item::from("pants:jeans")), &[]),
+                conflicts(ItemStatus::Available(Item::from("pants:slacks")), &[]),
+            ],
+            sorted(options["pants"].clone())
+        );
+    }
+
+    #[test]
+    fn excluded_items_report_conflicting_selections() {
+        let options = catalog().conflict_options(&[Item::from("pants:slacks")], &[]);
+
+        assert_eq!(
+            vec![
+                conflicts(ItemStatus::Implied(Item::from("shirts:blue")), &[]),
+                conflicts(ItemStatus::Excluded(Item::from("shirts:red")), &["pants:slacks"]),
+            ],
+            sorted(options["shirts"].clone())
+        );
+    }
+
+    #[test]
+    fn only_the_conflicting_subset_of_selections_is_reported() {
+        let options = catalog().conflict_options(&[Item::from("pants:jeans"), Item::from("shirts:red")], &[]);
+
+        assert_eq!(
+            vec![
+                conflicts(ItemStatus::Excluded(Item::from("shoes:loafers")), &["pants:jeans", "shirts:red"]),
+                conflicts(ItemStatus::Implied(Item::from("shoes:sneakers")), &[]),
+            ],
+            sorted(options["shoes"].clone())
+        );
+        assert_eq!(
+            vec![
+                conflicts(ItemStatus::Excluded(Item::from("shirts:blue")), &["shirts:red"]),
+                conflicts(ItemStatus::Selected(Item::from("shirts:red")), &[]),
+            ],
+            sorted(options["shirts"].clone())
+        );
+    }
+
+    #[test]
+    fn user_excluded_items_cannot_be_picked() {
+        let options = catalog().conflict_options(&[], &[Item::from("pants:slacks")]);
+
+        assert_eq!(
+            vec![
+                conflicts(ItemStatus::Implied(Item::from("pants:jeans")), &[]),
+                ItemConflicts { status: ItemStatus::UserExcluded(Item::from("pants:slacks")), conflicts: None },
+            ],
+            sorted(options["pants"].clone())
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, HashMap};
+
+use types::{Family, Item, NumericAttributes};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Comparison {
+    AtMost,
+    AtLeast,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Bound {
+    Value(f64),
+    Attribute { family: Family, attribute: String },
+}
+
+/// Compares an aggregate of a numeric attribute over an outfit against a bound.
+/// Only items in `families` are aggregated, or every item when `families` is empty.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AggregateConstraint {
+    attribute: String,
+    aggregate: Aggregate,
+    comparison: Comparison,
+    bound: Bound,
+    #[serde(default)]
+    families: Vec<Family>,
+}
+
+impl AggregateConstraint {
+    pub fn new(attribute: &str, aggregate: Aggregate, comparison: Comparison, bound: Bound, families: Vec<Family>) -> Self {
+        AggregateConstraint { attribute: attribute.into(), aggregate, comparison, bound, families }
+    }
+
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    pub fn families(&self) -> impl Iterator<Item=&Family> {
+        let bound_family = match self.bound {
+            Bound::Value(_) => None,
+            Bound::Attribute { ref family, .. } => Some(family),
+        };
+
+        self.families.iter().chain(bound_family)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item=&str> {
+        let bound_attribute = match self.bound {
+            Bound::Value(_) => None,
+            Bound::Attribute { ref attribute, .. } => Some(attribute.as_str()),
+        };
+
+        Some(self.attribute.as_str()).into_iter().chain(bound_attribute)
+    }
+
+    /// The items that violate the constraint on their own, when that is the only way to violate it:
+    /// a `Max` that is at most, or a `Min` that is at least, a fixed value.
+    /// `None` when the constraint depends on the whole outfit.
+    pub fn excluded_items(&self, item_families: &HashMap<Item, Family>, values: &BTreeMap<Item, NumericAttributes>) -> Option<Vec<Item>> {
+        let bound = match (self.aggregate, self.comparison, &self.bound) {
+            (Aggregate::Max, Comparison::AtMost, &Bound::Value(bound)) => bound,
+            (Aggregate::Min, Comparison::AtLeast, &Bound::Value(bound)) => bound,
+            _ => return None,
+        };
+
+        let excluded = item_families.iter()
+            .filter(|(_, family)| self.families.is_empty() || self.families.contains(family))
+            .filter_map(|(item, _)| values.get(item)
+                .and_then(|item_values| item_values.get(&self.attribute))
+                .map(|&value| (item, value)))
+            .filter(|&(_, value)| match self.comparison {
+                Comparison::AtMost => value > bound,
+                Comparison::AtLeast => value < bound,
+            })
+            .map(|(item, _)| item.clone())
+            .collect();
+
+        Some(excluded)
+    }
+
+    /// An outfit violates the constraint when the aggregate is out of bounds,
+    /// or when the bound is an attribute the outfit does not provide.
+    /// An aggregate over no values is zero for `Sum` and never violates `Min` or `Max`.
+    pub fn violated(&self, outfit: &[Item], item_families: &HashMap<Item, Family>, values: &BTreeMap<Item, NumericAttributes>) -> bool {
+        let in_scope = |item: &&Item| self.families.is_empty()
+            || item_families.get(*item).map_or(false, |family| self.families.contains(family));
+
+        let aggregated = outfit.iter()
+            .filter(in_scope)
+            .filter_map(|item| values.get(item).and_then(|item_values| item_values.get(&self.attribute)))
+            .cloned();
+
+        let aggregate = match self.aggregate {
+            Aggregate::Sum => Some(aggregated.sum::<f64>()),
+            Aggregate::Min => aggregated.fold(None, |min: Option<f64>, value| Some(min.map_or(value, |min| min.min(value)))),
+            Aggregate::Max => aggregated.fold(None, |max: Option<f64>, value| Some(max.map_or(value, |max| max.max(value)))),
+        };
+        let aggregate = match aggregate {
+            None => return false,
+            Some(aggregate) => aggregate,
+        };
+
+        let bound = match self.bound {
+            Bound::Value(value) => Some(value),
+            Bound::Attribute { ref family, ref attribute } => outfit.iter()
+                .filter(|item| item_families.get(*item) == Some(family))
+                .filter_map(|item| values.get(item).and_then(|item_values| item_values.get(attribute)))
+                .cloned()
+                .next(),
+        };
+
+        match (bound, self.comparison) {
+            (None, _) => true,
+            (Some(bound), Comparison::AtMost) => aggregate > bound,
+            (Some(bound), Comparison::AtLeast) => aggregate < bound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use catalog::fixtures::{catalog, priced, sorted_outfits};
+    use catalog::{CatalogError, CatalogState};
+    use types::{Family, Item, ItemStatus, NumericAttributes};
+
+    use super::{Aggregate, AggregateConstraint, Bound, Comparison};
+
+    fn item_families() -> HashMap<Item, Family> {
+        hashmap! {
+            Item::from("cpu:fast") => Family::from("cpu"),
+            Item::from("gpu:big") => Family::from("gpu"),
+            Item::from("psu:small") => Family::from("psu"),
+        }
+    }
+
+    fn values() -> BTreeMap<Item, NumericAttributes> {
+        btreemap! {
+            Item::from("cpu:fast") => btreemap! { "watts".into() => 95.0, "price".into() => 300.0 },
+            Item::from("gpu:big") => btreemap! { "watts".into() => 250.0, "price".into() => 700.0 },
+            Item::from("psu:small") => btreemap! { "capacity".into() => 300.0, "price".into() => 50.0 },
+        }
+    }
+
+    fn outfit() -> Vec<Item> {
+        vec![Item::from("cpu:fast"), Item::from("gpu:big"), Item::from("psu:small")]
+    }
+
+    #[test]
+    fn sum_over_all_items_is_compared_to_value() {
+        let within_budget = AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(1050.0), vec![]);
+        let over_budget = AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(1000.0), vec![]);
+
+        assert!(!within_budget.violated(&outfit(), &item_families(), &values()));
+        assert!(over_budget.violated(&outfit(), &item_families(), &values()));
+    }
+
+    #[test]
+    fn sum_over_families_is_compared_to_attribute_of_another_family() {
+        let wattage = AggregateConstraint::new(
+            "watts",
+            Aggregate::Sum,
+            Comparison::AtMost,
+            Bound::Attribute { family: "psu".into(), attribute: "capacity".into() },
+            vec!["cpu".into(), "gpu".into()],
+        );
+
+        assert!(wattage.violated(&outfit(), &item_families(), &values()));
+        assert!(!wattage.violated(&[Item::from("cpu:fast"), Item::from("psu:small")], &item_families(), &values()));
+    }
+
+    #[test]
+    fn max_at_most_and_min_at_least_a_value_exclude_single_items() {
+        let largest = AggregateConstraint::new("watts", Aggregate::Max, Comparison::AtMost, Bound::Value(200.0), vec![]);
+        let cheapest = AggregateConstraint::new("price", Aggregate::Min, Comparison::AtLeast, Bound::Value(100.0), vec!["cpu".into(), "psu".into()]);
+        let budget = AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(1000.0), vec![]);
+
+        assert_eq!(Some(vec![Item::from("gpu:big")]), largest.excluded_items(&item_families(), &values()));
+        assert_eq!(Some(vec![Item::from("psu:small")]), cheapest.excluded_items(&item_families(), &values()));
+        assert_eq!(None, budget.excluded_items(&item_families(), &values()));
+    }
+
+    #[test]
+    fn min_and_max_ignore_items_without_the_attribute() {
+        let cheapest = AggregateConstraint::new("watts", Aggregate::Min, Comparison::AtLeast, Bound::Value(95.0), vec![]);
+        let largest = AggregateConstraint::new("watts", Aggregate::Max, Comparison::AtMost, Bound::Value(200.0), vec![]);
+
+        assert!(!cheapest.violated(&outfit(), &item_families(), &values()));
+        assert!(largest.violated(&outfit(), &item_families(), &values()));
+        assert!(!largest.violated(&[Item::from("psu:small")], &item_families(), &values()));
+    }
+
+    fn state() -> CatalogState {
+        CatalogState::from_catalog(priced(catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans", "shirts:red"],
+            &["pants:slacks", "shirts:blue"],
+            &["pants:slacks", "shirts:red"],
+        ])))
+    }
+
+    fn budget(limit: f64) -> AggregateConstraint {
+        AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(limit), vec![])
+    }
+
+    #[test]
+    fn constraints_filter_outfits_at_query_time() {
+        let (combinations, _) = state()
+            .with_constraints(vec![budget(100.0)])
+            .combinations(&[], &[])
+            .expect("expected combinations");
+
+        assert_eq!(
+            vec![
+                vec![Item::from("pants:jeans"), Item::from("shirts:blue")],
+                vec![Item::from("pants:jeans"), Item::from("shirts:red")],
+                vec![Item::from("pants:slacks"), Item::from("shirts:blue")],
+            ],
+            sorted_outfits(combinations)
+        );
+    }
+
+    #[test]
+    fn constraints_are_kept_in_the_state() {
+        let (_, state) = state()
+            .with_constraints(vec![budget(90.0)])
+            .options(&[], &[])
+            .expect("expected options");
+
+        let (options, _) = state
+            .options(&[Item::from("shirts:red")], &[])
+            .expect("expected options");
+
+        assert_eq!(
+            vec![ItemStatus::Required(Item::from("pants:jeans")), ItemStatus::Excluded(Item::from("pants:slacks"))],
+            options["pants"]
+        );
+    }
+
+    #[test]
+    fn changed_constraints_apply_to_the_next_query() {
+        let (count, state) = state()
+            .with_constraints(vec![budget(90.0)])
+            .count(&[], &[])
+            .expect("expected count");
+        assert_eq!(2, count);
+
+        let state = CatalogState { constraints: vec![budget(100.0)], ..state };
+
+        let (count, _) = state.count(&[], &[]).expect("expected count");
+        assert_eq!(3, count);
+    }
+
+    #[test]
+    fn constraints_must_use_known_attributes() {
+        let constraint = AggregateConstraint::new("weight", Aggregate::Sum, Comparison::AtMost, Bound::Value(1.0), vec!["belts".into()]);
+
+        let error = state()
+            .with_constraints(vec![constraint])
+            .options(&[], &[])
+            .expect_err("expected options to return error");
+
+        match error {
+            CatalogError::UnknownConstraints { families, attributes } => {
+                assert_eq!(vec![Family::from("belts")], families);
+                assert_eq!(vec![String::from("weight")], attributes);
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+}
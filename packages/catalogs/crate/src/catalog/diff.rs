@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use weave::zdd2::Forest;
+
+use types::{Family, Item};
+
+use super::{Catalog, CatalogError, CatalogState, ExclusionRule, InclusionRule};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct MovedItem {
+    item: Item,
+    from: Family,
+    to: Family,
+}
+
+/// The rules only one side of a diff has.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RuleChanges<R> {
+    added: Vec<R>,
+    removed: Vec<R>,
+}
+
+impl<R: Clone + PartialEq> RuleChanges<R> {
+    pub fn between(before: &[R], after: &[R]) -> Self {
+        let added = after.iter().filter(|rule| !before.contains(rule)).cloned().collect();
+        let removed = before.iter().filter(|rule| !after.contains(rule)).cloned().collect();
+
+        RuleChanges { added, removed }
+    }
+
+    pub fn added(&self) -> &[R] {
+        &self.added
+    }
+
+    pub fn removed(&self) -> &[R] {
+        &self.removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// At most this many gained and lost outfits are listed in a diff.
+pub const LISTED_OUTFITS: usize = 50;
+
+/// What changed from one catalog to another. Items are compared by the `items` index,
+/// so an item that changed family shows up in `moved_items` rather than as removed and added.
+/// `outfits_gained` and `outfits_lost` count every changed outfit, but only the first `LISTED_OUTFITS` of each are listed.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CatalogDiff {
+    added_families: Vec<Family>,
+    removed_families: Vec<Family>,
+    added_items: Vec<Item>,
+    removed_items: Vec<Item>,
+    moved_items: Vec<MovedItem>,
+    exclusions: RuleChanges<ExclusionRule>,
+    inclusions: RuleChanges<InclusionRule>,
+    outfits_before: usize,
+    outfits_after: usize,
+    outfits_gained: usize,
+    outfits_lost: usize,
+    gained_outfits: Vec<Vec<Item>>,
+    lost_outfits: Vec<Vec<Item>>,
+}
+
+impl CatalogDiff {
+    pub fn added_families(&self) -> &[Family] {
+        &self.added_families
+    }
+
+    pub fn removed_families(&self) -> &[Family] {
+        &self.removed_families
+    }
+
+    pub fn added_items(&self) -> &[Item] {
+        &self.added_items
+    }
+
+    pub fn removed_items(&self) -> &[Item] {
+        &self.removed_items
+    }
+
+    pub fn moved_items(&self) -> &[MovedItem] {
+        &self.moved_items
+    }
+
+    pub fn exclusions(&self) -> &RuleChanges<ExclusionRule> {
+        &self.exclusions
+    }
+
+    pub fn inclusions(&self) -> &RuleChanges<InclusionRule> {
+        &self.inclusions
+    }
+
+    pub fn outfits_gained(&self) -> usize {
+        self.outfits_gained
+    }
+
+    pub fn outfits_lost(&self) -> usize {
+        self.outfits_lost
+    }
+
+    pub fn gained_outfits(&self) -> &[Vec<Item>] {
+        &self.gained_outfits
+    }
+
+    pub fn lost_outfits(&self) -> &[Vec<Item>] {
+        &self.lost_outfits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_families.is_empty()
+            && self.removed_families.is_empty()
+            && self.added_items.is_empty()
+            && self.removed_items.is_empty()
+            && self.moved_items.is_empty()
+            && self.exclusions.is_empty()
+            && self.inclusions.is_empty()
+            && self.outfits_gained == 0
+            && self.outfits_lost == 0
+    }
+}
+
+impl Catalog {
+    pub fn diff(&self, after: &Catalog) -> CatalogDiff {
+        let families_before = self.items.values().collect::<BTreeSet<_>>();
+        let families_after = after.items.values().collect::<BTreeSet<_>>();
+
+        let items_before = self.items.iter().collect::<BTreeMap<_, _>>();
+        let items_after = after.items.iter().collect::<BTreeMap<_, _>>();
+
+        let moved_items = items_before.iter()
+            .filter_map(|(&item, &from)| match items_after.get(item) {
+                Some(&to) if to != from => Some(MovedItem { item: item.clone(), from: from.clone(), to: to.clone() }),
+                _ => None,
+            })
+            .collect();
+
+        let (gained, lost) = self.outfit_changes(after);
+
+        CatalogDiff {
+            added_families: families_after.difference(&families_before).map(|&family| family.clone()).collect(),
+            removed_families: families_before.difference(&families_after).map(|&family| family.clone()).collect(),
+            added_items: items_after.keys().filter(|item| !items_before.contains_key(*item)).map(|&item| item.clone()).collect(),
+            removed_items: items_before.keys().filter(|item| !items_after.contains_key(*item)).map(|&item| item.clone()).collect(),
+            moved_items,
+            exclusions: RuleChanges::between(self.rules.exclusions(), after.rules.exclusions()),
+            inclusions: RuleChanges::between(self.rules.inclusions(), after.rules.inclusions()),
+            outfits_before: self.combinations.len(),
+            outfits_after: after.combinations.len(),
+            outfits_gained: gained.len(),
+            outfits_lost: lost.len(),
+            gained_outfits: after.listed(gained),
+            lost_outfits: self.listed(lost),
+        }
+    }
+
+    /// How many outfits `after` has that this catalog does not, and how many it no longer has.
+    pub fn outfits_gained_and_lost(&self, after: &Catalog) -> (usize, usize) {
+        let (gained, lost) = self.outfit_changes(after);
+
+        (gained.len(), lost.len())
+    }
+
+    fn outfit_changes(&self, after: &Catalog) -> (Forest<Item>, Forest<Item>) {
+        let gained = after.combinations.difference(&self.combinations);
+        let lost = self.combinations.difference(&after.combinations);
+
+        (gained, lost)
+    }
+
+    fn listed(&self, outfits: Forest<Item>) -> Vec<Vec<Item>> {
+        self.cheapest(outfits, |_| 0.0, LISTED_OUTFITS)
+            .into_iter()
+            .map(|(outfit, _)| outfit)
+            .collect()
+    }
+}
+
+impl CatalogState {
+    /// Compares the catalogs two states were built from, ignoring their selections and exclusions.
+    pub fn diff(&self, after: &CatalogState) -> Result<CatalogDiff, CatalogError> {
+        let before = Self::catalog_from_token(&self.token)?;
+        let after = Self::catalog_from_token(&after.token)?;
+
+        Ok(before.diff(&after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{catalog, catalog_with_families, items, shirts_and_pants as summer};
+    use catalog::{CatalogRules, ExclusionRule};
+
+    use super::{MovedItem, LISTED_OUTFITS};
+
+    #[test]
+    fn identical_catalogs_have_an_empty_diff() {
+        assert!(summer().diff(&summer()).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_families_and_items() {
+        let autumn = catalog_with_families(
+            &[
+                &["pants:jeans", "shirts:blue", "shoes:boots"],
+                &["pants:jeans", "shirts:red", "shoes:boots"],
+            ],
+            &[("pants:jeans", "pants"), ("shirts:blue", "shirts"), ("shirts:red", "tops"), ("shoes:boots", "shoes")],
+        );
+
+        let diff = summer().diff(&autumn);
+
+        assert_eq!(&["shoes".to_string(), "tops".to_string()], diff.added_families());
+        assert!(diff.removed_families().is_empty());
+        assert_eq!(items(&["shoes:boots"]), diff.added_items());
+        assert_eq!(items(&["pants:slacks"]), diff.removed_items());
+        assert_eq!(
+            &[MovedItem { item: "shirts:red".into(), from: "shirts".into(), to: "tops".into() }],
+            diff.moved_items()
+        );
+    }
+
+    #[test]
+    fn diff_reports_rules_and_outfits() {
+        let exclusion = ExclusionRule::new(vec![items(&["shirts:red"])], items(&["pants:jeans"]));
+        let autumn = catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:slacks", "shirts:blue"],
+            &["pants:slacks", "shirts:red"],
+        ]).with_rules(CatalogRules::new(vec![exclusion.clone()], vec![]));
+
+        let diff = summer().diff(&autumn);
+
+        assert_eq!(&[exclusion], diff.exclusions().added());
+        assert!(diff.exclusions().removed().is_empty());
+        assert_eq!(&[items(&["pants:slacks", "shirts:red"])], diff.gained_outfits());
+        assert_eq!(&[items(&["pants:jeans", "shirts:red"])], diff.lost_outfits());
+    }
+
+    #[test]
+    fn listed_outfits_are_capped_but_counted() {
+        let shirts = (0..8).map(|shirt| format!("shirts:{}", shirt)).collect::<Vec<_>>();
+        let pants = (0..8).map(|pants| format!("pants:{}", pants)).collect::<Vec<_>>();
+        let outfits = shirts.iter()
+            .flat_map(|shirt| pants.iter().map(move |pants| vec![pants.as_str(), shirt.as_str()]))
+            .collect::<Vec<_>>();
+        let outfits = outfits.iter().map(|outfit| outfit.as_slice()).collect::<Vec<_>>();
+
+        let diff = summer().diff(&catalog(&outfits));
+
+        assert_eq!(64, diff.outfits_gained());
+        assert_eq!(3, diff.outfits_lost());
+        assert_eq!(LISTED_OUTFITS, diff.gained_outfits().len());
+        assert_eq!(3, diff.lost_outfits().len());
+    }
+}
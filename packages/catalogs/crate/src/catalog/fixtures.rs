@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use weave::zdd2::Forest;
+
+use types::{Family, Item};
+
+use super::Catalog;
+
+pub fn items(items: &[&str]) -> Vec<Item> {
+    items.iter().map(|&item| Item::from(item)).collect()
+}
+
+pub fn catalog(outfits: &[&[&str]]) -> Catalog {
+    let families = outfits.iter()
+        .flat_map(|outfit| outfit.iter())
+        .map(|&item| (item, item.split(':').next().unwrap()))
+        .collect::<Vec<_>>();
+
+    catalog_with_families(outfits, &families)
+}
+
+/// A catalog whose items may belong to any family, or to no outfit at all.
+pub fn catalog_with_families(outfits: &[&[&str]], families: &[(&str, &str)]) -> Catalog {
+    let combinations = outfits.iter()
+        .map(|&outfit| items(outfit))
+        .collect::<Vec<_>>();
+    let items: HashMap<Item, Family> = families.iter()
+        .map(|&(item, family)| (Item::from(item), Family::from(family)))
+        .collect();
+
+    Catalog::new(Forest::many(&combinations), items)
+}
+
+/// Every shirt goes with jeans, but slacks only go with blue.
+pub fn shirts_and_pants() -> Catalog {
+    catalog(&[
+        &["pants:jeans", "shirts:blue"],
+        &["pants:jeans", "shirts:red"],
+        &["pants:slacks", "shirts:blue"],
+    ])
+}
+
+/// Adds a price to the shirts and pants.
+pub fn priced(catalog: Catalog) -> Catalog {
+    catalog.with_numeric_attributes(btreemap! {
+        Item::from("shirts:blue") => btreemap! { "price".into() => 20.0 },
+        Item::from("shirts:red") => btreemap! { "price".into() => 30.0 },
+        Item::from("pants:jeans") => btreemap! { "price".into() => 50.0 },
+        Item::from("pants:slacks") => btreemap! { "price".into() => 80.0 },
+    })
+}
+
+pub fn sorted<T: Ord>(mut values: Vec<T>) -> Vec<T> {
+    values.sort();
+    values
+}
+
+pub fn sorted_outfits(outfits: Vec<Vec<Item>>) -> Vec<Vec<Item>> {
+    sorted(outfits.into_iter().map(sorted).collect())
+}
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+
+use types::{Family, Item};
+
+use super::Catalog;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FamilyRecommendation {
+    family: Family,
+    entropy: f64,
+    choices: usize,
+}
+
+impl Catalog {
+    /// Ranks the families that still have more than one possible item by the entropy of their item counts,
+    /// so the family whose answer narrows the remaining outfits the most comes first.
+    pub fn recommend_families(self, selections: &[Item], exclusions: &[Item]) -> Vec<FamilyRecommendation> {
+        let catalog = self.restrict(selections, exclusions);
+        let total = catalog.combinations.len();
+        if total == 0 {
+            return vec![];
+        }
+
+        catalog.item_occurrences()
+            .filter(|(_, (_, count))| *count > 0)
+            .map(|(family, (_, count))| (family, count))
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, counts)| counts.len() > 1)
+            .map(|(family, counts)| {
+                let choices = counts.len();
+                let missing = total - counts.iter().sum::<usize>().min(total);
+                let entropy = counts.into_iter()
+                    .chain(Some(missing))
+                    .filter(|count| *count > 0)
+                    .map(|count| count as f64 / total as f64)
+                    .map(|share| -share * share.log2())
+                    .sum();
+
+                FamilyRecommendation { family, entropy, choices }
+            })
+            .sorted_by(|a, b| {
+                b.entropy.partial_cmp(&a.entropy)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| a.family.cmp(&b.family))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures;
+    use types::{Family, Item};
+
+    use super::Catalog;
+
+    fn catalog() -> Catalog {
+        fixtures::catalog(&[
+            &["pants:jeans", "shirts:blue", "shoes:sneakers"],
+            &["pants:jeans", "shirts:red", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:sneakers"],
+            &["pants:slacks", "shirts:red", "shoes:loafers"],
+        ])
+    }
+
+    fn ranking(catalog: Catalog, selections: &[Item]) -> Vec<(Family, usize)> {
+        catalog.recommend_families(selections, &[])
+            .into_iter()
+            .map(|recommendation| (recommendation.family, recommendation.choices))
+            .collect()
+    }
+
+    #[test]
+    fn evenly_split_families_are_recommended_first() {
+        assert_eq!(
+            vec![(Family::from("pants"), 2), (Family::from("shirts"), 2), (Family::from("shoes"), 2)],
+            ranking(catalog(), &[])
+        );
+
+        let recommendations = catalog().recommend_families(&[], &[]);
+        assert_eq!(1.0, recommendations[0].entropy);
+        assert!(recommendations[2].entropy < 1.0);
+    }
+
+    #[test]
+    fn decided_families_are_not_recommended() {
+        assert_eq!(
+            vec![(Family::from("shirts"), 2)],
+            ranking(catalog(), &[Item::from("pants:jeans")])
+        );
+    }
+
+    #[test]
+    fn no_families_are_recommended_for_a_complete_outfit() {
+        assert_eq!(
+            Vec::<(Family, usize)>::new(),
+            ranking(catalog(), &[Item::from("pants:slacks"), Item::from("shirts:red")])
+        );
+    }
+}
@@ -1,22 +1,56 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
+use std::mem;
 
 use itertools::Itertools;
 use serde::{Serialize, Serializer};
 use wasm_bindgen::prelude::*;
 use weave::zdd2::Forest;
 
+use errors::{CatalogsError, StatusClass};
 use types::Family;
 use types::Item;
 use types::ItemStatus;
+use types::NumericAttributes;
 
 use self::CatalogError::{UnknownExclusions, UnknownItems, UnknownSelections};
-
-#[derive(Serialize, Deserialize, Debug)]
+pub use self::completion::CompletionMode;
+pub use self::conflicts::{ConflictOptions, ItemConflicts};
+pub use self::constraints::{Aggregate, AggregateConstraint, Bound, Comparison};
+pub use self::diff::{CatalogDiff, MovedItem, RuleChanges};
+pub use self::guidance::FamilyRecommendation;
+pub use self::optimization::{ItemPrice, Objective, Optimization, PriceRange, PricedOptions, RankedOutfit};
+pub use self::options::{CountedOptions, FamilyOptions, FamilyOptionsByFamily, ItemCount, OptionsQuery};
+pub(crate) use self::options::group_statuses;
+pub use self::rebase::RebasedState;
+pub use self::repair::{RepairedOutfit, Substitution};
+pub use self::rules::{AttributeRelation, AttributeRule, CatalogRule, CatalogRules, ExclusionRule, InclusionRule};
+pub use self::validation::{OutfitValidation, RuleViolation};
+
+mod completion;
+mod conflicts;
+mod constraints;
+mod diff;
+/// Catalogs shared by the tests, with items named "family:item".
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod guidance;
+mod optimization;
+mod options;
+mod rebase;
+mod repair;
+mod rules;
+mod search;
+mod validation;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Catalog {
     combinations: Forest<Item>,
     #[serde(serialize_with = "ordered_map")]
     items: HashMap<Item, Family>,
+    numeric_attributes: BTreeMap<Item, NumericAttributes>,
+    rules: CatalogRules,
 }
 
 fn ordered_map<S: Serializer>(value: &HashMap<Item, Family>, serializer: S) -> Result<S::Ok, S::Error> {
@@ -26,7 +60,15 @@ fn ordered_map<S: Serializer>(value: &HashMap<Item, Family>, serializer: S) -> R
 
 impl Catalog {
     pub fn new(combinations: Forest<Item>, items: HashMap<Item, Family>) -> Self {
-        Catalog { combinations, items }
+        Catalog { combinations, items, numeric_attributes: BTreeMap::new(), rules: CatalogRules::default() }
+    }
+
+    pub fn with_numeric_attributes(self, numeric_attributes: BTreeMap<Item, NumericAttributes>) -> Self {
+        Catalog { numeric_attributes, ..self }
+    }
+
+    pub fn with_rules(self, rules: CatalogRules) -> Self {
+        Catalog { rules, ..self }
     }
 
     pub fn restrict(self, selections: &[Item], exclusions: &[Item]) -> Self {
@@ -34,15 +76,75 @@ impl Catalog {
             .subset_all(selections)
             .subset_none(exclusions);
 
-        Catalog { combinations, items: self.items }
+        Catalog { combinations, ..self }
+    }
+
+    /// Takes the outfits `restrict` would keep out of the catalog, leaving it none, so the forest is never cloned.
+    /// The catalog keeps its items and attributes for reading the outfits taken.
+    pub(crate) fn take_restricted(&mut self, selections: &[Item], exclusions: &[Item]) -> Forest<Item> {
+        mem::replace(&mut self.combinations, Forest::many(&[]))
+            .subset_all(selections)
+            .subset_none(exclusions)
+    }
+
+    /// The items in every outfit, which stay `Required` however the outfits are restricted.
+    pub(crate) fn required_items(&self) -> HashSet<Item> {
+        let total = self.combinations.len();
+
+        self.combinations.occurrences()
+            .into_iter()
+            .filter(|&(_, count)| count == total)
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    /// Constraints that single items violate on their own are applied to the forest directly;
+    /// only the rest need every remaining outfit checked.
+    pub fn constrain(self, constraints: &[AggregateConstraint]) -> Self {
+        let mut excluded = vec![];
+        let mut outfit_constraints = vec![];
+        for constraint in constraints {
+            match constraint.excluded_items(&self.items, &self.numeric_attributes) {
+                Some(items) => excluded.extend(items),
+                None => outfit_constraints.push(constraint),
+            }
+        }
+
+        let catalog = if excluded.is_empty() { self } else { self.restrict(&[], &excluded) };
+        if outfit_constraints.is_empty() {
+            return catalog;
+        }
+
+        let combinations = catalog.combinations.trees()
+            .into_iter()
+            .filter(|outfit| {
+                !outfit_constraints.iter()
+                    .any(|constraint| constraint.violated(outfit, &catalog.items, &catalog.numeric_attributes))
+            })
+            .collect::<Vec<_>>();
+
+        Catalog { combinations: Forest::many(&combinations), ..catalog }
     }
 
     pub fn combinations(&self) -> Vec<Vec<Item>> {
         self.combinations.trees()
     }
 
+    pub(crate) fn forest(&self) -> &Forest<Item> {
+        &self.combinations
+    }
+
+    pub fn outfit_count(&self) -> usize {
+        self.combinations.len()
+    }
+
     pub fn item_occurrences<'a>(&'a self) -> impl Iterator<Item=(Family, (Item, usize))> + 'a {
-        self.combinations.occurrences()
+        self.occurrences_in(&self.combinations)
+    }
+
+    /// Like `item_occurrences`, counting the outfits in `forest` instead of every outfit in the catalog.
+    pub(crate) fn occurrences_in<'a>(&'a self, forest: &Forest<Item>) -> impl Iterator<Item=(Family, (Item, usize))> + 'a {
+        forest.occurrences()
             .into_iter()
             .map(move |(item, count)| {
                 let family = self.items[&item].clone();
@@ -55,6 +157,59 @@ impl Catalog {
             .unique_by(|(_, (item, _))| item.clone())
     }
 
+    pub fn validate_items(&self, selections: &[Item], exclusions: &[Item]) -> Result<(), CatalogError> {
+        let unknown_selections = self.not_recognized(selections);
+        let unknown_exclusions = self.not_recognized(exclusions);
+
+        match (unknown_selections.len(), unknown_exclusions.len()) {
+            (0, 0) => Ok(()),
+            (_, 0) => Err(UnknownSelections { items: unknown_selections }),
+            (0, _) => Err(UnknownExclusions { items: unknown_exclusions }),
+            (_, _) => Err(UnknownItems {
+                selections: unknown_selections,
+                exclusions: unknown_exclusions,
+            }),
+        }
+    }
+
+    pub fn validate_constraints(&self, constraints: &[AggregateConstraint]) -> Result<(), CatalogError> {
+        let known_families = self.items.values().collect::<HashSet<_>>();
+        let known_attributes = self.numeric_attributes.values()
+            .flat_map(|values| values.keys())
+            .map(|attribute| attribute.as_str())
+            .collect::<HashSet<_>>();
+
+        let families = constraints.iter()
+            .flat_map(|constraint| constraint.families())
+            .filter(|family| !known_families.contains(family))
+            .cloned()
+            .unique()
+            .collect::<Vec<_>>();
+        let attributes = constraints.iter()
+            .flat_map(|constraint| constraint.attributes())
+            .filter(|attribute| !known_attributes.contains(attribute))
+            .map(String::from)
+            .unique()
+            .collect::<Vec<_>>();
+
+        if families.is_empty() && attributes.is_empty() {
+            Ok(())
+        } else {
+            Err(CatalogError::UnknownConstraints { families, attributes })
+        }
+    }
+
+    pub fn validate_attribute(&self, attribute: &str) -> Result<(), CatalogError> {
+        let known = self.numeric_attributes.values()
+            .any(|values| values.contains_key(attribute));
+
+        if known {
+            Ok(())
+        } else {
+            Err(CatalogError::UnknownAttribute { attribute: attribute.into() })
+        }
+    }
+
     fn not_recognized(&self, items: &[Item]) -> Vec<Item> {
         items.iter()
             .filter(|&item| !self.items.contains_key(item))
@@ -69,18 +224,118 @@ pub enum CatalogError {
     UnknownSelections { items: Vec<Item> },
     UnknownExclusions { items: Vec<Item> },
     BadState,
+    BadArgument { argument: String },
     BadToken { token: String, detail: String },
+    UnsupportedToken { token: String, version: u8 },
     UnknownItems { selections: Vec<Item>, exclusions: Vec<Item> },
+    UnknownConstraints { families: Vec<Family>, attributes: Vec<String> },
+    UnknownAttribute { attribute: String },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl CatalogError {
+    pub(crate) fn bad_argument(argument: &str) -> Self {
+        CatalogError::BadArgument { argument: argument.into() }
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::UnknownSelections { items } => write!(f, "unknown selections: {}", items.join(", ")),
+            CatalogError::UnknownExclusions { items } => write!(f, "unknown exclusions: {}", items.join(", ")),
+            CatalogError::BadState => write!(f, "catalog state could not be read"),
+            CatalogError::BadArgument { argument } => write!(f, "{} could not be read", argument),
+            CatalogError::BadToken { detail, .. } => write!(f, "catalog token could not be read: {}", detail),
+            CatalogError::UnsupportedToken { version, .. } => write!(f, "catalog token version {} is not supported", version),
+            CatalogError::UnknownItems { selections, exclusions } =>
+                write!(f, "unknown selections: {}; unknown exclusions: {}", selections.join(", "), exclusions.join(", ")),
+            CatalogError::UnknownConstraints { families, attributes } =>
+                write!(f, "constraints on unknown families: {}; unknown attributes: {}", families.join(", "), attributes.join(", ")),
+            CatalogError::UnknownAttribute { attribute } => write!(f, "unknown attribute: {}", attribute),
+        }
+    }
+}
+
+impl Error for CatalogError {}
+
+impl CatalogsError for CatalogError {
+    fn code(&self) -> &'static str {
+        match self {
+            CatalogError::UnknownSelections { .. } => "UNKNOWN_SELECTIONS",
+            CatalogError::UnknownExclusions { .. } => "UNKNOWN_EXCLUSIONS",
+            CatalogError::BadState => "BAD_STATE",
+            CatalogError::BadArgument { .. } => "BAD_ARGUMENT",
+            CatalogError::BadToken { .. } => "BAD_TOKEN",
+            CatalogError::UnsupportedToken { .. } => "UNSUPPORTED_TOKEN",
+            CatalogError::UnknownItems { .. } => "UNKNOWN_ITEMS",
+            CatalogError::UnknownConstraints { .. } => "UNKNOWN_CONSTRAINTS",
+            CatalogError::UnknownAttribute { .. } => "UNKNOWN_ATTRIBUTE",
+        }
+    }
+
+    /// A token that cannot be read was sent to us malformed, while one of a version we do not read
+    /// is well formed but needs the catalog to be built again.
+    fn status(&self) -> StatusClass {
+        match self {
+            CatalogError::UnsupportedToken { .. } => StatusClass::FailedPrecondition,
+            _ => StatusClass::InvalidArgument,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CatalogToken(String);
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Tokens start with this prefix and a format version, followed by the bincoded catalog.
+/// Tokens without the prefix were written before the catalog kept attributes and rules, and hold a `LegacyCatalog`.
+const TOKEN_PREFIX: &[u8] = b"IGNC";
+const TOKEN_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct LegacyCatalog {
+    combinations: Forest<Item>,
+    items: HashMap<Item, Family>,
+}
+
+impl From<LegacyCatalog> for Catalog {
+    fn from(legacy: LegacyCatalog) -> Self {
+        Catalog::new(legacy.combinations, legacy.items)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CatalogVersion {
+    version: u64,
+    hash: String,
+}
+
+impl CatalogVersion {
+    pub fn new(version: u64, hash: &str) -> Self {
+        CatalogVersion { version, hash: hash.into() }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// The token always holds the whole catalog as it was built; the selections, exclusions and constraints
+/// are applied to it on every query, so `Required` can still be told apart from `Implied`.
+/// Tokens from before the version prefix held the catalog already restricted by the state's choices.
+/// Restricting them again changes nothing, but items their choices implied are then reported as `Required`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CatalogState {
     token: CatalogToken,
     selections: Vec<Item>,
     exclusions: Vec<Item>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<AggregateConstraint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<CatalogVersion>,
 }
 
 pub type OptionsByFamily = BTreeMap<Family, Vec<ItemStatus<Item>>>;
@@ -92,113 +347,200 @@ impl CatalogState {
     }
 
     pub fn from_catalog(catalog: Catalog) -> Self {
+        Self::new(&catalog, vec![], vec![])
+    }
+
+    pub fn new(catalog: &Catalog, selections: Vec<Item>, exclusions: Vec<Item>) -> Self {
         Self {
-            token: Self::catalog_to_token(&catalog),
-            selections: vec![],
-            exclusions: vec![],
+            token: Self::catalog_to_token(catalog),
+            selections,
+            exclusions,
+            constraints: vec![],
+            version: None,
         }
     }
 
-    pub fn combinations(self, selections: &[Item], exclusions: &[Item]) -> Result<(Vec<Vec<Item>>, Self), CatalogError> {
-        let catalog = Self::catalog_from_token(&self.token)?;
-        Self::validate_selections_and_exclusions(&catalog, selections, exclusions)?;
+    pub fn with_constraints(self, constraints: Vec<AggregateConstraint>) -> Self {
+        CatalogState { constraints, ..self }
+    }
 
-        let selections = Self::chain(&self.selections, selections);
-        let exclusions = Self::chain(&self.exclusions, exclusions);
+    /// Replaces the selections and exclusions without checking them against the catalog.
+    pub fn with_choices(self, selections: Vec<Item>, exclusions: Vec<Item>) -> Self {
+        CatalogState { selections, exclusions, ..self }
+    }
 
-        let catalog = catalog.restrict(&selections, &exclusions);
-        let combinations = catalog.combinations();
+    /// Records the stored version of the catalog this state was built from.
+    pub fn with_version(self, version: CatalogVersion) -> Self {
+        CatalogState { version: Some(version), ..self }
+    }
 
-        let new_state = CatalogState {
-            token: Self::catalog_to_token(&catalog),
-            selections,
-            exclusions,
-        };
+    pub fn version(&self) -> Option<&CatalogVersion> {
+        self.version.as_ref()
+    }
+
+    /// A hash of the compiled catalog, so identical catalogs hash the same whatever their version.
+    /// Uses FNV-1a, which unlike `DefaultHasher` is stable across Rust releases and safe to persist.
+    pub fn content_hash(&self) -> String {
+        let hash = self.token.0.bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+
+        format!("{:016x}", hash)
+    }
+
+    pub fn combinations(self, selections: &[Item], exclusions: &[Item]) -> Result<(Vec<Vec<Item>>, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let combinations = catalog
+            .restrict(&new_state.selections, &new_state.exclusions)
+            .combinations();
 
         Ok((combinations, new_state))
     }
 
+    pub fn count(self, selections: &[Item], exclusions: &[Item]) -> Result<(usize, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let count = catalog
+            .restrict(&new_state.selections, &new_state.exclusions)
+            .outfit_count();
+
+        Ok((count, new_state))
+    }
+
     pub fn options(self, selections: &[Item], exclusions: &[Item]) -> Result<(OptionsByFamily, Self), CatalogError> {
-        let catalog = Self::catalog_from_token(&self.token)?;
-        Self::validate_selections_and_exclusions(&catalog, selections, exclusions)?;
-
-        let selections = Self::chain(&self.selections, selections);
-        let exclusions = Self::chain(&self.exclusions, exclusions);
-
-        let catalog = catalog.restrict(&selections, &exclusions);
-        let total = catalog.combinations.len();
-
-        let options = catalog.item_occurrences()
-            .map(|(family, (item, count))| {
-                let item = if count == 0 {
-                    ItemStatus::Excluded(item)
-                } else if selections.contains(&item) {
-                    ItemStatus::Selected(item)
-                } else if count == total {
-                    ItemStatus::Required(item)
-                } else {
-                    ItemStatus::Available(item)
-                };
-
-                (family, item)
-            })
-            .into_group_map::<Family, ItemStatus<Item>>()
-            .into_iter()
-            .collect::<BTreeMap<_, _>>();
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let options = catalog.options(&new_state.selections, &new_state.exclusions);
 
-        let new_state = CatalogState {
-            token: Self::catalog_to_token(&catalog),
-            selections,
-            exclusions,
-        };
+        Ok((options, new_state))
+    }
+
+    pub fn counted_options(self, selections: &[Item], exclusions: &[Item]) -> Result<(CountedOptions, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let options = catalog.counted_options(&new_state.selections, &new_state.exclusions);
+
+        Ok((options, new_state))
+    }
+
+    pub fn family_options(self, selections: &[Item], exclusions: &[Item]) -> Result<(FamilyOptionsByFamily, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let options = catalog.family_options(&new_state.selections, &new_state.exclusions);
+
+        Ok((options, new_state))
+    }
+
+    pub fn conflict_options(self, selections: &[Item], exclusions: &[Item]) -> Result<(ConflictOptions, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let options = catalog.conflict_options(&new_state.selections, &new_state.exclusions);
 
         Ok((options, new_state))
     }
 
+    pub fn complete(self, selections: &[Item], exclusions: &[Item], mode: CompletionMode) -> Result<(Option<Vec<Item>>, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let outfit = catalog.complete(&new_state.selections, &new_state.exclusions, mode);
+
+        Ok((outfit, new_state))
+    }
+
+    pub fn optimize(self, selections: &[Item], exclusions: &[Item], optimization: &Optimization) -> Result<(Vec<RankedOutfit>, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        catalog.validate_attribute(optimization.attribute())?;
+        let outfits = catalog.optimize(&new_state.selections, &new_state.exclusions, optimization);
+
+        Ok((outfits, new_state))
+    }
+
+    pub fn priced_options(self, selections: &[Item], exclusions: &[Item], attribute: &str) -> Result<(PricedOptions, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        catalog.validate_attribute(attribute)?;
+        let options = catalog.priced_options(&new_state.selections, &new_state.exclusions, attribute);
+
+        Ok((options, new_state))
+    }
+
+    pub fn recommend_families(self, selections: &[Item], exclusions: &[Item]) -> Result<(Vec<FamilyRecommendation>, Self), CatalogError> {
+        let (catalog, new_state) = self.apply(selections, exclusions)?;
+        let recommendations = catalog.recommend_families(&new_state.selections, &new_state.exclusions);
+
+        Ok((recommendations, new_state))
+    }
+
+    pub fn validate_outfit(&self, outfit: &[Item]) -> Result<OutfitValidation, CatalogError> {
+        let catalog = self.catalog()?;
+
+        Ok(catalog.validate_outfit(outfit, &self.constraints))
+    }
+
+    pub fn repair_outfit(&self, outfit: &[Item], pinned: &[Item]) -> Result<Option<RepairedOutfit>, CatalogError> {
+        let catalog = self.catalog()?;
+        catalog.validate_items(pinned, &[])?;
+
+        Ok(catalog.repair(outfit, pinned))
+    }
+
+    pub fn selections(&self) -> &[Item] {
+        &self.selections
+    }
+
+    pub fn exclusions(&self) -> &[Item] {
+        &self.exclusions
+    }
+
+    pub fn constraints(&self) -> &[AggregateConstraint] {
+        &self.constraints
+    }
+
+    pub fn catalog(&self) -> Result<Catalog, CatalogError> {
+        let catalog = Self::catalog_from_token(&self.token)?;
+        catalog.validate_constraints(&self.constraints)?;
+
+        Ok(catalog.constrain(&self.constraints))
+    }
+
     pub fn catalog_from_token(catalog_token: &CatalogToken) -> Result<Catalog, CatalogError> {
         let catalog_token = &catalog_token.0;
-        let decoded_token = match base64::decode(catalog_token.as_str()) {
-            Ok(t) => t,
-            Err(err) => return Err(CatalogError::BadToken {
-                token: catalog_token.clone(),
-                detail: err.description().into(),
-            })
+        let bad_token = |detail: &str| CatalogError::BadToken {
+            token: catalog_token.clone(),
+            detail: detail.into(),
         };
 
-        let catalog: Catalog = match bincode::deserialize(&decoded_token[..]) {
-            Ok(c) => c,
-            Err(err) => {
-                return Err(CatalogError::BadToken {
-                    token: catalog_token.clone(),
-                    detail: err.description().into(),
-                });
-            }
-        };
+        let decoded_token = base64::decode(catalog_token.as_str())
+            .map_err(|err| bad_token(err.description()))?;
 
-        Ok(catalog)
+        if !decoded_token.starts_with(TOKEN_PREFIX) {
+            return bincode::deserialize::<LegacyCatalog>(&decoded_token[..])
+                .map(Catalog::from)
+                .map_err(|err| bad_token(err.description()));
+        }
+
+        match decoded_token.get(TOKEN_PREFIX.len()) {
+            Some(&TOKEN_VERSION) => bincode::deserialize(&decoded_token[TOKEN_PREFIX.len() + 1..])
+                .map_err(|err| bad_token(err.description())),
+            Some(&version) => Err(CatalogError::UnsupportedToken { token: catalog_token.clone(), version }),
+            None => Err(bad_token("missing token version")),
+        }
     }
 
-    fn catalog_to_token(catalog: &Catalog) -> CatalogToken {
-        let bytes = bincode::serialize(catalog).unwrap();
-        CatalogToken(base64::encode(&bytes[..]))
+    fn apply(self, selections: &[Item], exclusions: &[Item]) -> Result<(Catalog, Self), CatalogError> {
+        let catalog = self.catalog()?;
+        catalog.validate_items(selections, exclusions)?;
+
+        let new_state = CatalogState {
+            selections: Self::chain(&self.selections, selections),
+            exclusions: Self::chain(&self.exclusions, exclusions),
+            ..self
+        };
+
+        Ok((catalog, new_state))
     }
 
-    fn validate_selections_and_exclusions(catalog: &Catalog, selections: &[Item], exclusions: &[Item]) -> Result<(), CatalogError> {
-        let unknown_selections = catalog.not_recognized(selections);
-        let unknown_exclusions = catalog.not_recognized(exclusions);
+    fn catalog_to_token(catalog: &Catalog) -> CatalogToken {
+        let mut bytes = TOKEN_PREFIX.to_vec();
+        bytes.push(TOKEN_VERSION);
+        bytes.extend(bincode::serialize(catalog).unwrap());
 
-        match (unknown_selections.len(), unknown_exclusions.len()) {
-            (0, 0) => Ok(()),
-            (_, 0) => Err(UnknownSelections { items: unknown_selections }),
-            (0, _) => Err(UnknownExclusions { items: unknown_exclusions }),
-            (_, _) => Err(UnknownItems {
-                selections: unknown_selections,
-                exclusions: unknown_exclusions,
-            }),
-        }
+        CatalogToken(base64::encode(&bytes[..]))
     }
 
-    fn chain(v1: &[Item], v2: &[Item]) -> Vec<Item> {
+    pub fn chain(v1: &[Item], v2: &[Item]) -> Vec<Item> {
         v1.iter()
             .chain(v2)
             .cloned()
@@ -207,3 +549,92 @@ impl CatalogState {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use errors::{CatalogsError, StatusClass};
+
+    use catalog::fixtures::{priced, shirts_and_pants, sorted_outfits};
+
+    use types::ItemStatus;
+
+    use super::{Catalog, CatalogError, CatalogState, CatalogToken, LegacyCatalog};
+
+    fn legacy_token(catalog: &Catalog) -> CatalogToken {
+        let legacy = LegacyCatalog { combinations: catalog.combinations.clone(), items: catalog.items.clone() };
+
+        CatalogToken(base64::encode(&bincode::serialize(&legacy).unwrap()[..]))
+    }
+
+    #[test]
+    fn errors_report_stable_codes_and_statuses() {
+        let err = CatalogError::UnknownSelections { items: vec!["shirts:green".into()] };
+        assert_eq!("UNKNOWN_SELECTIONS", err.code());
+        assert_eq!(StatusClass::InvalidArgument, err.status());
+        assert_eq!("unknown selections: shirts:green", err.to_string());
+
+        let err = CatalogError::BadArgument { argument: "queries".into() };
+        assert_eq!("BAD_ARGUMENT", err.code());
+        assert_eq!(StatusClass::InvalidArgument, err.status());
+        assert_eq!("queries could not be read", err.to_string());
+
+        let err = CatalogError::BadToken { token: "bad".into(), detail: "Invalid byte".into() };
+        assert_eq!("BAD_TOKEN", err.code());
+        assert_eq!(StatusClass::InvalidArgument, err.status());
+
+        let err = CatalogError::UnsupportedToken { token: "IGNC".into(), version: 9 };
+        assert_eq!("UNSUPPORTED_TOKEN", err.code());
+        assert_eq!(StatusClass::FailedPrecondition, err.status());
+    }
+
+    #[test]
+    fn tokens_round_trip_the_whole_catalog() {
+        let catalog = priced(shirts_and_pants());
+        let token = CatalogState::catalog_to_token(&catalog);
+
+        let decoded = CatalogState::catalog_from_token(&token).expect("expected token to decode");
+
+        assert_eq!(sorted_outfits(catalog.combinations()), sorted_outfits(decoded.combinations()));
+        assert_eq!(catalog.numeric_attributes, decoded.numeric_attributes);
+    }
+
+    #[test]
+    fn tokens_from_before_the_version_prefix_still_decode() {
+        let catalog = shirts_and_pants();
+        let token = legacy_token(&catalog);
+
+        let decoded = CatalogState::catalog_from_token(&token).expect("expected legacy token to decode");
+
+        assert_eq!(sorted_outfits(catalog.combinations()), sorted_outfits(decoded.combinations()));
+        assert_eq!(catalog.items, decoded.items);
+        assert!(decoded.numeric_attributes.is_empty());
+    }
+
+    #[test]
+    fn legacy_tokens_with_choices_reapply_them() {
+        let restricted = shirts_and_pants().restrict(&["shirts:red".into()], &[]);
+        let token = legacy_token(&restricted);
+
+        let state = CatalogState { token, selections: vec!["shirts:red".into()], exclusions: vec![], constraints: vec![], version: None };
+        let (options, state) = state.options(&[], &[]).expect("expected legacy token to be read");
+
+        assert_eq!(
+            btreemap! {
+                "pants".into() => vec![ItemStatus::Required("pants:jeans".into()), ItemStatus::Excluded("pants:slacks".into())],
+                "shirts".into() => vec![ItemStatus::Selected("shirts:red".into()), ItemStatus::Excluded("shirts:blue".into())],
+            },
+            options
+        );
+        assert_eq!(&["shirts:red".to_string()][..], state.selections());
+    }
+
+    #[test]
+    fn tokens_with_an_unknown_version_are_rejected() {
+        let token = CatalogToken(base64::encode(b"IGNC\x09"));
+
+        match CatalogState::catalog_from_token(&token) {
+            Err(CatalogError::UnsupportedToken { version, .. }) => assert_eq!(9, version),
+            result => panic!("unexpected result {:?}", result.map(|catalog| catalog.combinations())),
+        }
+    }
+}
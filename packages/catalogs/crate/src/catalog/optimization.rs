@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use types::{Family, Item, ItemStatus};
+
+use super::Catalog;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Objective {
+    Minimize,
+    Maximize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Optimization {
+    attribute: String,
+    objective: Objective,
+    #[serde(default = "Optimization::default_limit")]
+    limit: usize,
+}
+
+impl Optimization {
+    pub fn new(attribute: &str, objective: Objective, limit: usize) -> Self {
+        Optimization { attribute: attribute.into(), objective, limit }
+    }
+
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    fn default_limit() -> usize {
+        1
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankedOutfit {
+    outfit: Vec<Item>,
+    score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PriceRange {
+    min: f64,
+    max: f64,
+}
+
+impl PriceRange {
+    fn including(range: Option<PriceRange>, score: f64) -> PriceRange {
+        match range {
+            None => PriceRange { min: score, max: score },
+            Some(PriceRange { min, max }) => PriceRange { min: min.min(score), max: max.max(score) },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemPrice {
+    status: ItemStatus<Item>,
+    range: Option<PriceRange>,
+}
+
+pub type PricedOptions = BTreeMap<Family, Vec<ItemPrice>>;
+
+impl Catalog {
+    /// Ranks the outfits remaining after the selections and exclusions by the sum of an attribute.
+    /// Items without the attribute contribute nothing, and ties are ordered by their items family by family.
+    pub fn optimize(mut self, selections: &[Item], exclusions: &[Item], optimization: &Optimization) -> Vec<RankedOutfit> {
+        let sign = match optimization.objective {
+            Objective::Minimize => 1.0,
+            Objective::Maximize => -1.0,
+        };
+        let forest = self.take_restricted(selections, exclusions);
+        let cost = |item: &Item| sign * self.value(item, &optimization.attribute);
+
+        self.cheapest(forest, cost, optimization.limit)
+            .into_iter()
+            .map(|(outfit, _)| {
+                let score = self.score(&outfit, &optimization.attribute);
+                RankedOutfit { outfit, score }
+            })
+            .collect()
+    }
+
+    /// Reports, for every item, the lowest and highest outfit total of an attribute over the remaining outfits containing it.
+    /// Items in no remaining outfit have no range.
+    pub fn priced_options(mut self, selections: &[Item], exclusions: &[Item], attribute: &str) -> PricedOptions {
+        let required = self.required_items();
+        let forest = self.take_restricted(selections, exclusions);
+        let cheapest = |outfits, sign: f64| self.cheapest(outfits, |item: &Item| sign * self.value(item, attribute), 1)
+            .into_iter()
+            .next()
+            .map(|(outfit, _)| self.score(&outfit, attribute));
+
+        self.restricted_item_statuses(&forest, &required, selections, exclusions)
+            .into_iter()
+            .map(|(family, (status, count))| {
+                let range = if count == 0 {
+                    None
+                } else {
+                    let outfits = forest.clone().subset_all(&[status.item().clone()]);
+                    let min = cheapest(outfits.clone(), 1.0);
+                    let max = cheapest(outfits, -1.0);
+
+                    min.into_iter().chain(max).fold(None, |range, score| Some(PriceRange::including(range, score)))
+                };
+
+                (family, ItemPrice { status, range })
+            })
+            .into_group_map::<Family, ItemPrice>()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    }
+
+    fn score(&self, outfit: &[Item], attribute: &str) -> f64 {
+        outfit.iter()
+            .map(|item| self.value(item, attribute))
+            .sum()
+    }
+
+    fn value(&self, item: &Item, attribute: &str) -> f64 {
+        self.numeric_attributes.get(item)
+            .and_then(|values| values.get(attribute))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{self, priced};
+    use catalog::{CatalogError, CatalogState};
+    use types::{Item, ItemStatus};
+
+    use super::{Catalog, ItemPrice, Objective, Optimization, PriceRange, RankedOutfit};
+
+    fn catalog() -> Catalog {
+        priced(fixtures::catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans", "shirts:red"],
+            &["pants:slacks", "shirts:red"],
+        ]))
+    }
+
+    fn ranked(outfit: &[&str], score: f64) -> RankedOutfit {
+        RankedOutfit { outfit: outfit.iter().map(|&item| Item::from(item)).collect(), score }
+    }
+
+    #[test]
+    fn minimize_returns_cheapest_outfits_first() {
+        let outfits = catalog().optimize(&[], &[], &Optimization::new("price", Objective::Minimize, 2));
+
+        assert_eq!(
+            vec![
+                ranked(&["pants:jeans", "shirts:blue"], 70.0),
+                ranked(&["pants:jeans", "shirts:red"], 80.0),
+            ],
+            outfits
+        );
+    }
+
+    #[test]
+    fn maximize_respects_selections_and_exclusions() {
+        let outfits = catalog().optimize(
+            &[Item::from("shirts:red")],
+            &[Item::from("pants:slacks")],
+            &Optimization::new("price", Objective::Maximize, 5),
+        );
+
+        assert_eq!(vec![ranked(&["pants:jeans", "shirts:red"], 80.0)], outfits);
+    }
+
+    #[test]
+    fn maximize_returns_single_best_outfit() {
+        let outfits = catalog().optimize(&[], &[], &Optimization::new("price", Objective::Maximize, 1));
+
+        assert_eq!(vec![ranked(&["pants:slacks", "shirts:red"], 110.0)], outfits);
+    }
+
+    #[test]
+    fn optimizing_an_unknown_attribute_is_an_error() {
+        let error = CatalogState::from_catalog(catalog())
+            .optimize(&[], &[], &Optimization::new("weight", Objective::Minimize, 1))
+            .expect_err("expected optimize to return error");
+
+        match error {
+            CatalogError::UnknownAttribute { attribute } => assert_eq!("weight", attribute),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn priced_options_report_outfit_totals_per_item() {
+        let options = catalog().priced_options(&[], &[], "price");
+
+        assert_eq!(
+            vec![
+                ItemPrice { status: ItemStatus::Available(Item::from("shirts:blue")), range: Some(PriceRange { min: 70.0, max: 70.0 }) },
+                ItemPrice { status: ItemStatus::Available(Item::from("shirts:red")), range: Some(PriceRange { min: 80.0, max: 110.0 }) },
+            ],
+            sorted(options["shirts"].clone())
+        );
+    }
+
+    #[test]
+    fn priced_options_have_no_range_for_excluded_items() {
+        let options = catalog().priced_options(&[Item::from("shirts:blue")], &[], "price");
+
+        assert_eq!(
+            vec![
+                ItemPrice { status: ItemStatus::Implied(Item::from("pants:jeans")), range: Some(PriceRange { min: 70.0, max: 70.0 }) },
+                ItemPrice { status: ItemStatus::Excluded(Item::from("pants:slacks")), range: None },
+            ],
+            sorted(options["pants"].clone())
+        );
+    }
+
+    fn sorted(mut prices: Vec<ItemPrice>) -> Vec<ItemPrice> {
+        prices.sort_by(|a, b| a.status.item().cmp(b.status.item()));
+        prices
+    }
+}
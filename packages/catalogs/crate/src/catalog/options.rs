@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashSet};
+
+use itertools::Itertools;
+use weave::zdd2::Forest;
+
+use types::{Family, FamilyStatus, Item, ItemStatus};
+
+use super::{Catalog, CatalogError, CatalogState, OptionsByFamily};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ItemCount {
+    status: ItemStatus<Item>,
+    count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CountedOptions {
+    total: usize,
+    options: BTreeMap<Family, Vec<ItemCount>>,
+}
+
+impl ItemCount {
+    pub fn status(&self) -> &ItemStatus<Item> {
+        &self.status
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl CountedOptions {
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn options(&self) -> &BTreeMap<Family, Vec<ItemCount>> {
+        &self.options
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct FamilyOptions {
+    status: FamilyStatus,
+    remaining: usize,
+    items: Vec<ItemStatus<Item>>,
+}
+
+pub type FamilyOptionsByFamily = BTreeMap<Family, FamilyOptions>;
+
+impl FamilyOptions {
+    fn new(items: Vec<ItemStatus<Item>>) -> Self {
+        let remaining = items.iter()
+            .filter(|item| match item {
+                ItemStatus::Excluded(_) | ItemStatus::UserExcluded(_) => false,
+                _ => true,
+            })
+            .count();
+
+        let decided = items.iter()
+            .any(|item| match item {
+                ItemStatus::Selected(_) | ItemStatus::Required(_) | ItemStatus::Implied(_) => true,
+                _ => false,
+            });
+
+        let status = if remaining == 0 {
+            FamilyStatus::Exhausted
+        } else if decided {
+            FamilyStatus::Decided
+        } else {
+            FamilyStatus::Open
+        };
+
+        FamilyOptions { status, remaining, items }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OptionsQuery {
+    #[serde(default)]
+    selections: Vec<Item>,
+    #[serde(default)]
+    exclusions: Vec<Item>,
+}
+
+impl OptionsQuery {
+    pub fn new(selections: Vec<Item>, exclusions: Vec<Item>) -> Self {
+        OptionsQuery { selections, exclusions }
+    }
+}
+
+impl Catalog {
+    pub fn options(self, selections: &[Item], exclusions: &[Item]) -> OptionsByFamily {
+        group_statuses(self.item_statuses(selections, exclusions))
+    }
+
+    pub fn counted_options(mut self, selections: &[Item], exclusions: &[Item]) -> CountedOptions {
+        let required = self.required_items();
+        let restricted = self.take_restricted(selections, exclusions);
+        let total = restricted.len();
+
+        let options = self.restricted_item_statuses(&restricted, &required, selections, exclusions)
+            .into_iter()
+            .map(|(family, (status, count))| (family, ItemCount { status, count }))
+            .into_group_map::<Family, ItemCount>()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+
+        CountedOptions { total, options }
+    }
+
+    pub fn family_options(self, selections: &[Item], exclusions: &[Item]) -> FamilyOptionsByFamily {
+        self.options(selections, exclusions)
+            .into_iter()
+            .map(|(family, items)| (family, FamilyOptions::new(items)))
+            .collect()
+    }
+
+    pub(super) fn item_statuses(mut self, selections: &[Item], exclusions: &[Item]) -> Vec<(Family, (ItemStatus<Item>, usize))> {
+        let required = self.required_items();
+        let restricted = self.take_restricted(selections, exclusions);
+
+        self.restricted_item_statuses(&restricted, &required, selections, exclusions)
+    }
+
+    /// Like `item_statuses`, for outfits the caller already restricted by the selections and exclusions,
+    /// given the items the unrestricted catalog required.
+    pub(crate) fn restricted_item_statuses(&self, restricted: &Forest<Item>, required: &HashSet<Item>, selections: &[Item], exclusions: &[Item]) -> Vec<(Family, (ItemStatus<Item>, usize))> {
+        let total = restricted.len();
+
+        self.occurrences_in(restricted)
+            .map(|(family, (item, count))| {
+                let status = if exclusions.contains(&item) {
+                    ItemStatus::UserExcluded(item)
+                } else if count == 0 {
+                    ItemStatus::Excluded(item)
+                } else if selections.contains(&item) {
+                    ItemStatus::Selected(item)
+                } else if count == total && required.contains(&item) {
+                    ItemStatus::Required(item)
+                } else if count == total {
+                    ItemStatus::Implied(item)
+                } else {
+                    ItemStatus::Available(item)
+                };
+
+                (family, (status, count))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn group_statuses(statuses: Vec<(Family, (ItemStatus<Item>, usize))>) -> OptionsByFamily {
+    statuses.into_iter()
+        .map(|(family, (status, _))| (family, status))
+        .into_group_map::<Family, ItemStatus<Item>>()
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+}
+
+impl CatalogState {
+    /// Every query restricts its own copy of the outfits, while the catalog is decoded and checked for required items once.
+    pub fn batch_options(&self, queries: &[OptionsQuery]) -> Result<Vec<OptionsByFamily>, CatalogError> {
+        let catalog = self.catalog()?;
+        let required = catalog.required_items();
+
+        queries.iter()
+            .map(|query| {
+                catalog.validate_items(&query.selections, &query.exclusions)?;
+
+                let selections = Self::chain(&self.selections, &query.selections);
+                let exclusions = Self::chain(&self.exclusions, &query.exclusions);
+                let restricted = catalog.combinations.clone()
+                    .subset_all(&selections)
+                    .subset_none(&exclusions);
+
+                Ok(group_statuses(catalog.restricted_item_statuses(&restricted, &required, &selections, &exclusions)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{catalog, catalog_with_families, shirts_and_pants, sorted};
+    use catalog::{Catalog, CatalogError, CatalogState};
+    use types::{FamilyStatus, Item, ItemStatus};
+
+    use super::{ItemCount, OptionsQuery};
+
+    /// Jeans are the only pants that go with anything.
+    fn jeans_only() -> Catalog {
+        catalog_with_families(
+            &[&["pants:jeans", "shirts:blue"], &["pants:jeans", "shirts:red"]],
+            &[("pants:jeans", "pants"), ("pants:slacks", "pants"), ("shirts:blue", "shirts"), ("shirts:red", "shirts")],
+        )
+    }
+
+    #[test]
+    fn batch_options_returns_one_result_per_query() {
+        let state = CatalogState::from_catalog(shirts_and_pants());
+
+        let options = state.batch_options(&[
+            OptionsQuery::new(vec![], vec![]),
+            OptionsQuery::new(vec![Item::from("shirts:red")], vec![]),
+            OptionsQuery::new(vec![], vec![Item::from("pants:jeans")]),
+        ])
+            .expect("expected batch to return options");
+
+        assert_eq!(3, options.len());
+        assert_eq!(
+            vec![ItemStatus::Excluded(Item::from("pants:slacks")), ItemStatus::Implied(Item::from("pants:jeans"))],
+            sorted(options[1]["pants"].clone())
+        );
+        assert_eq!(
+            vec![ItemStatus::UserExcluded(Item::from("pants:jeans")), ItemStatus::Implied(Item::from("pants:slacks"))],
+            sorted(options[2]["pants"].clone())
+        );
+    }
+
+    #[test]
+    fn batch_options_matches_single_options() {
+        let selections = vec![Item::from("pants:slacks")];
+
+        let expected = CatalogState::from_catalog(shirts_and_pants())
+            .options(&selections, &[])
+            .map(|(options, _)| options)
+            .expect("expected options");
+
+        let options = CatalogState::from_catalog(shirts_and_pants())
+            .batch_options(&[OptionsQuery::new(selections, vec![])])
+            .expect("expected batch to return options");
+
+        assert_eq!(vec![expected], options);
+    }
+
+    #[test]
+    fn batch_options_returns_error_for_unknown_items() {
+        let state = CatalogState::from_catalog(shirts_and_pants());
+
+        let error = state.batch_options(&[
+            OptionsQuery::new(vec![], vec![]),
+            OptionsQuery::new(vec![Item::from("shirts:black")], vec![]),
+        ])
+            .expect_err("expected batch to return error");
+
+        match error {
+            CatalogError::UnknownSelections { items } => assert_eq!(vec![Item::from("shirts:black")], items),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn counted_options_include_outfit_counts() {
+        let (options, _) = CatalogState::from_catalog(shirts_and_pants())
+            .counted_options(&[], &[])
+            .expect("expected counted options");
+
+        assert_eq!(3, options.total);
+
+        let mut shirts = options.options["shirts"].clone();
+        shirts.sort_by_key(|item| item.count);
+        assert_eq!(
+            vec![
+                ItemCount { status: ItemStatus::Available(Item::from("shirts:red")), count: 1 },
+                ItemCount { status: ItemStatus::Available(Item::from("shirts:blue")), count: 2 },
+            ],
+            shirts
+        );
+    }
+
+    #[test]
+    fn counted_options_respect_selections() {
+        let (options, _) = CatalogState::from_catalog(shirts_and_pants())
+            .counted_options(&[Item::from("pants:jeans")], &[])
+            .expect("expected counted options");
+
+        assert_eq!(2, options.total);
+
+        let mut pants = options.options["pants"].clone();
+        pants.sort_by_key(|item| item.count);
+        assert_eq!(
+            vec![
+                ItemCount { status: ItemStatus::Excluded(Item::from("pants:slacks")), count: 0 },
+                ItemCount { status: ItemStatus::Selected(Item::from("pants:jeans")), count: 2 },
+            ],
+            pants
+        );
+    }
+
+    #[test]
+    fn items_required_by_the_catalog_are_required() {
+        let (options, _) = CatalogState::from_catalog(jeans_only())
+            .options(&[], &[])
+            .expect("expected options");
+
+        assert_eq!(
+            vec![ItemStatus::Required(Item::from("pants:jeans")), ItemStatus::Excluded(Item::from("pants:slacks"))],
+            sorted(options["pants"].clone())
+        );
+    }
+
+    #[test]
+    fn items_forced_by_exclusions_are_implied() {
+        let (options, _) = CatalogState::from_catalog(jeans_only())
+            .options(&[], &[Item::from("shirts:red")])
+            .expect("expected options");
+
+        assert_eq!(
+            vec![ItemStatus::UserExcluded(Item::from("shirts:red")), ItemStatus::Implied(Item::from("shirts:blue"))],
+            sorted(options["shirts"].clone())
+        );
+        assert_eq!(
+            vec![ItemStatus::Required(Item::from("pants:jeans")), ItemStatus::Excluded(Item::from("pants:slacks"))],
+            sorted(options["pants"].clone())
+        );
+    }
+
+    #[test]
+    fn user_exclusions_are_kept_across_states() {
+        let (_, state) = CatalogState::from_catalog(jeans_only())
+            .options(&[], &[Item::from("pants:slacks")])
+            .expect("expected options");
+
+        let (options, _) = state
+            .options(&[], &[])
+            .expect("expected options");
+
+        assert_eq!(
+            vec![ItemStatus::Required(Item::from("pants:jeans")), ItemStatus::UserExcluded(Item::from("pants:slacks"))],
+            sorted(options["pants"].clone())
+        );
+    }
+
+    fn with_black_shirt() -> Catalog {
+        catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans", "shirts:red"],
+            &["pants:jeans", "shirts:black"],
+            &["pants:slacks", "shirts:blue"],
+        ])
+    }
+
+    #[test]
+    fn families_without_selections_are_open() {
+        let (options, _) = CatalogState::from_catalog(with_black_shirt())
+            .family_options(&[], &[])
+            .expect("expected family options");
+
+        assert_eq!(FamilyStatus::Open, options["shirts"].status);
+        assert_eq!(3, options["shirts"].remaining);
+        assert_eq!(FamilyStatus::Open, options["pants"].status);
+        assert_eq!(2, options["pants"].remaining);
+    }
+
+    #[test]
+    fn families_with_a_selection_are_decided() {
+        let (options, _) = CatalogState::from_catalog(with_black_shirt())
+            .family_options(&[Item::from("shirts:red")], &[])
+            .expect("expected family options");
+
+        assert_eq!(FamilyStatus::Decided, options["shirts"].status);
+        assert_eq!(1, options["shirts"].remaining);
+        assert_eq!(FamilyStatus::Decided, options["pants"].status);
+        assert_eq!(1, options["pants"].remaining);
+    }
+
+    #[test]
+    fn families_without_remaining_items_are_exhausted() {
+        let (options, _) = CatalogState::from_catalog(with_black_shirt())
+            .family_options(&[Item::from("pants:slacks")], &[Item::from("shirts:blue")])
+            .expect("expected family options");
+
+        assert_eq!(FamilyStatus::Exhausted, options["shirts"].status);
+        assert_eq!(0, options["shirts"].remaining);
+        assert_eq!(FamilyStatus::Exhausted, options["pants"].status);
+        assert_eq!(0, options["pants"].remaining);
+    }
+}
@@ -0,0 +1,157 @@
+use types::Item;
+
+use super::{AggregateConstraint, Catalog, CatalogError, CatalogState};
+
+/// A state moved onto another catalog, with everything that could not come along.
+/// `conflicting_selections` are known items that can no longer be picked together with the rest of the state.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebasedState {
+    state: CatalogState,
+    missing_selections: Vec<Item>,
+    missing_exclusions: Vec<Item>,
+    conflicting_selections: Vec<Item>,
+    dropped_constraints: Vec<AggregateConstraint>,
+}
+
+impl RebasedState {
+    pub fn state(&self) -> &CatalogState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> CatalogState {
+        self.state
+    }
+
+    pub fn missing_selections(&self) -> &[Item] {
+        &self.missing_selections
+    }
+
+    pub fn missing_exclusions(&self) -> &[Item] {
+        &self.missing_exclusions
+    }
+
+    pub fn conflicting_selections(&self) -> &[Item] {
+        &self.conflicting_selections
+    }
+
+    pub fn dropped_constraints(&self) -> &[AggregateConstraint] {
+        &self.dropped_constraints
+    }
+
+    /// True when the whole state came along unchanged.
+    pub fn is_clean(&self) -> bool {
+        self.missing_selections.is_empty()
+            && self.missing_exclusions.is_empty()
+            && self.conflicting_selections.is_empty()
+            && self.dropped_constraints.is_empty()
+    }
+}
+
+impl CatalogState {
+    /// Reapplies this state's selections, exclusions and constraints to `catalog`.
+    /// Items the catalog no longer knows are dropped, and then as few selections as it takes for an outfit to remain.
+    /// Which selections stay is found by searching the forest for the outfit with the most of them, the first family by family on ties.
+    pub fn rebase(&self, catalog: &Catalog) -> RebasedState {
+        let (known_selections, missing_selections) = partition(&self.selections, |item| catalog.items.contains_key(item));
+        let (known_exclusions, missing_exclusions) = partition(&self.exclusions, |item| catalog.items.contains_key(item));
+        let (constraints, dropped_constraints) = partition(&self.constraints, |constraint| {
+            catalog.validate_constraints(&[constraint.clone()]).is_ok()
+        });
+
+        let mut constrained = catalog.clone().constrain(&constraints);
+        let forest = constrained.take_restricted(&[], &known_exclusions);
+        let kept = |item: &Item| if known_selections.contains(item) { -1.0 } else { 0.0 };
+        let kept = constrained.cheapest(forest, kept, 1)
+            .into_iter()
+            .next()
+            .map_or_else(Vec::new, |(outfit, _)| outfit);
+        let (kept_selections, conflicting_selections) = partition(&known_selections, |selection| kept.contains(selection));
+
+        let state = CatalogState::new(catalog, kept_selections, known_exclusions)
+            .with_constraints(constraints);
+
+        RebasedState { state, missing_selections, missing_exclusions, conflicting_selections, dropped_constraints }
+    }
+
+    /// Rebases onto the catalog `target` was built from, keeping the version `target` records.
+    pub fn rebase_onto(&self, target: &CatalogState) -> Result<RebasedState, CatalogError> {
+        let catalog = Self::catalog_from_token(&target.token)?;
+
+        let mut rebased = self.rebase(&catalog);
+        rebased.state.version = target.version.clone();
+        Ok(rebased)
+    }
+}
+
+fn partition<T: Clone, F: Fn(&T) -> bool>(values: &[T], keep: F) -> (Vec<T>, Vec<T>) {
+    values.iter().cloned().partition(|value| keep(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{catalog, items};
+    use catalog::{Catalog, CatalogState, CatalogVersion};
+
+    fn summer() -> Catalog {
+        catalog(&[
+            &["pants:jeans", "shirts:blue", "shoes:sneakers"],
+            &["pants:jeans", "shirts:red", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+            &["pants:shorts", "shirts:red", "shoes:sandals"],
+        ])
+    }
+
+    #[test]
+    fn state_moves_unchanged_when_everything_still_fits() {
+        let state = CatalogState::new(&summer(), items(&["shirts:blue"]), items(&["pants:slacks"]));
+
+        let rebased = state.rebase(&summer());
+
+        assert!(rebased.is_clean());
+        assert_eq!(items(&["shirts:blue"]), rebased.state().selections());
+        assert_eq!(items(&["pants:slacks"]), rebased.state().exclusions());
+    }
+
+    #[test]
+    fn removed_items_are_reported_missing() {
+        let state = CatalogState::new(&summer(), items(&["pants:shorts", "shirts:red"]), items(&["shoes:sandals"]));
+        let autumn = catalog(&[
+            &["pants:jeans", "shirts:red", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+        ]);
+
+        let rebased = state.rebase(&autumn);
+
+        assert_eq!(items(&["pants:shorts"]), rebased.missing_selections());
+        assert_eq!(items(&["shoes:sandals"]), rebased.missing_exclusions());
+        assert!(rebased.conflicting_selections().is_empty());
+        assert_eq!(items(&["shirts:red"]), rebased.state().selections());
+        assert!(rebased.state().exclusions().is_empty());
+    }
+
+    #[test]
+    fn selections_that_no_longer_go_together_are_reported_conflicting() {
+        let state = CatalogState::new(&summer(), items(&["pants:jeans", "shirts:blue", "shoes:sneakers"]), vec![]);
+        let autumn = catalog(&[
+            &["pants:jeans", "shirts:red", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+        ]);
+
+        let rebased = state.rebase(&autumn);
+
+        assert!(rebased.missing_selections().is_empty());
+        assert_eq!(items(&["shirts:blue"]), rebased.conflicting_selections());
+        assert_eq!(items(&["pants:jeans", "shoes:sneakers"]), rebased.state().selections());
+    }
+
+    #[test]
+    fn rebasing_onto_a_state_keeps_its_version() {
+        let state = CatalogState::new(&summer(), items(&["shirts:blue"]), vec![]);
+        let target = CatalogState::from_catalog(summer()).with_version(CatalogVersion::new(2, "abc"));
+
+        let rebased = state.rebase_onto(&target).unwrap();
+
+        assert_eq!(Some(&CatalogVersion::new(2, "abc")), rebased.state().version());
+        assert_eq!(items(&["shirts:blue"]), rebased.state().selections());
+    }
+}
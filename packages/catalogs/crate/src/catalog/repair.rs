@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use types::{Family, Item};
+
+use super::Catalog;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Substitution {
+    family: Family,
+    from: Option<Item>,
+    to: Item,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RepairedOutfit {
+    outfit: Vec<Item>,
+    substitutions: Vec<Substitution>,
+}
+
+impl Catalog {
+    /// Finds the valid outfit that keeps the pinned items and changes the fewest families of `outfit`,
+    /// searching the forest with every item outside `outfit` costing one change.
+    /// Ties are broken by the first outfit, comparing items family by family; `None` when no outfit keeps the pinned items.
+    pub fn repair(mut self, outfit: &[Item], pinned: &[Item]) -> Option<RepairedOutfit> {
+        let forest = self.take_restricted(pinned, &[]);
+        let picks: BTreeMap<Family, &Item> = outfit.iter()
+            .filter_map(|item| self.items.get(item).map(|family| (family.clone(), item)))
+            .rev()
+            .collect();
+
+        let substitutions = |candidate: &[Item]| candidate.iter()
+            .filter(|item| !outfit.contains(item))
+            .map(|item| {
+                let family = self.items[item].clone();
+                let from = picks.get(&family).map(|&from| from.clone());
+
+                Substitution { family, from, to: item.clone() }
+            })
+            .sorted_by(|a, b| a.family.cmp(&b.family))
+            .collect::<Vec<_>>();
+
+        let changes = |item: &Item| if outfit.contains(item) { 0.0 } else { 1.0 };
+
+        self.cheapest(forest, changes, 1)
+            .into_iter()
+            .next()
+            .map(|(candidate, _)| {
+                let substitutions = substitutions(&candidate);
+
+                RepairedOutfit { outfit: candidate, substitutions }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures;
+    use types::Item;
+
+    use super::{Catalog, RepairedOutfit, Substitution};
+
+    fn catalog() -> Catalog {
+        fixtures::catalog(&[
+            &["pants:jeans", "shirts:blue", "shoes:sneakers"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+            &["pants:slacks", "shirts:red", "shoes:loafers"],
+        ])
+    }
+
+    fn substitution(family: &str, from: Option<&str>, to: &str) -> Substitution {
+        Substitution { family: family.into(), from: from.map(Item::from), to: to.into() }
+    }
+
+    #[test]
+    fn valid_outfit_needs_no_substitutions() {
+        let outfit = vec![Item::from("pants:jeans"), Item::from("shirts:blue"), Item::from("shoes:sneakers")];
+
+        assert_eq!(
+            Some(RepairedOutfit { outfit: outfit.clone(), substitutions: vec![] }),
+            catalog().repair(&outfit, &[])
+        );
+    }
+
+    #[test]
+    fn invalid_outfit_is_repaired_with_fewest_substitutions() {
+        let outfit = vec![Item::from("pants:jeans"), Item::from("shirts:red"), Item::from("shoes:loafers")];
+
+        assert_eq!(
+            Some(RepairedOutfit {
+                outfit: vec![Item::from("pants:slacks"), Item::from("shirts:red"), Item::from("shoes:loafers")],
+                substitutions: vec![substitution("pants", Some("pants:jeans"), "pants:slacks")],
+            }),
+            catalog().repair(&outfit, &[])
+        );
+    }
+
+    #[test]
+    fn pinned_items_are_not_substituted() {
+        let outfit = vec![Item::from("pants:jeans"), Item::from("shirts:red"), Item::from("shoes:loafers")];
+
+        assert_eq!(
+            Some(RepairedOutfit {
+                outfit: vec![Item::from("pants:jeans"), Item::from("shirts:blue"), Item::from("shoes:sneakers")],
+                substitutions: vec![
+                    substitution("shirts", Some("shirts:red"), "shirts:blue"),
+                    substitution("shoes", Some("shoes:loafers"), "shoes:sneakers"),
+                ],
+            }),
+            catalog().repair(&outfit, &[Item::from("pants:jeans")])
+        );
+    }
+
+    #[test]
+    fn missing_families_are_filled_in() {
+        let outfit = vec![Item::from("shirts:red")];
+
+        assert_eq!(
+            Some(RepairedOutfit {
+                outfit: vec![Item::from("pants:slacks"), Item::from("shirts:red"), Item::from("shoes:loafers")],
+                substitutions: vec![
+                    substitution("pants", None, "pants:slacks"),
+                    substitution("shoes", None, "shoes:loafers"),
+                ],
+            }),
+            catalog().repair(&outfit, &[])
+        );
+    }
+
+    #[test]
+    fn conflicting_pins_cannot_be_repaired() {
+        let outfit = vec![Item::from("pants:jeans"), Item::from("shirts:red")];
+
+        assert_eq!(None, catalog().repair(&outfit, &[Item::from("pants:jeans"), Item::from("shirts:red")]));
+    }
+}
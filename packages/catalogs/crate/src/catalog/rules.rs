@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use weave::zdd2::Forest;
+
+use types::Item;
+
+use super::AggregateConstraint;
+
+pub trait CatalogRule {
+    fn check(&self, outfit: &[Item]) -> bool;
+
+    fn has_conditions(&self) -> bool;
+
+    /// Removes every outfit `check` would reject, without looking at the outfits one by one.
+    fn remove_violations(&self, outfits: Forest<Item>) -> Forest<Item>;
+}
+
+/// The outfits with at least one of `items`.
+fn with_any(outfits: &Forest<Item>, items: &[Item]) -> Forest<Item> {
+    outfits.difference(&outfits.clone().subset_none(items))
+}
+
+/// The outfits meeting every condition, each met by any one of its items.
+fn meeting(outfits: &Forest<Item>, conditions: &[Vec<Item>]) -> Forest<Item> {
+    conditions.iter().fold(outfits.clone(), |met, condition| with_any(&met, condition))
+}
+
+/// An exclusion rule with its targets resolved to items.
+/// Each condition is satisfied by any one of its items.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ExclusionRule {
+    conditions: Vec<Vec<Item>>,
+    exclusions: Vec<Item>,
+}
+
+impl ExclusionRule {
+    pub fn new(conditions: Vec<Vec<Item>>, exclusions: Vec<Item>) -> Self {
+        ExclusionRule { conditions, exclusions }
+    }
+
+    pub fn conditions(&self) -> &[Vec<Item>] {
+        &self.conditions
+    }
+
+    pub fn exclusions(&self) -> &[Item] {
+        &self.exclusions
+    }
+}
+
+impl CatalogRule for ExclusionRule {
+    fn check(&self, outfit: &[Item]) -> bool {
+        self.conditions.iter().all(|condition| condition.iter().any(|item| outfit.contains(item)))
+            && self.exclusions.iter().any(|item| outfit.contains(item))
+    }
+
+    fn has_conditions(&self) -> bool {
+        !self.conditions.is_empty()
+    }
+
+    fn remove_violations(&self, outfits: Forest<Item>) -> Forest<Item> {
+        let broken = with_any(&meeting(&outfits, &self.conditions), &self.exclusions);
+
+        outfits.difference(&broken)
+    }
+}
+
+/// An inclusion rule with its targets resolved to items.
+/// Each condition and each inclusion is satisfied by any one of its items.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct InclusionRule {
+    conditions: Vec<Vec<Item>>,
+    inclusions: Vec<Vec<Item>>,
+}
+
+impl InclusionRule {
+    pub fn new(conditions: Vec<Vec<Item>>, inclusions: Vec<Vec<Item>>) -> Self {
+        InclusionRule { conditions, inclusions }
+    }
+
+    pub fn conditions(&self) -> &[Vec<Item>] {
+        &self.conditions
+    }
+
+    pub fn inclusions(&self) -> &[Vec<Item>] {
+        &self.inclusions
+    }
+}
+
+impl CatalogRule for InclusionRule {
+    fn check(&self, outfit: &[Item]) -> bool {
+        self.conditions.iter().all(|condition| condition.iter().any(|item| outfit.contains(item)))
+            && self.inclusions.iter().any(|inclusion| !inclusion.iter().any(|item| outfit.contains(item)))
+    }
+
+    fn has_conditions(&self) -> bool {
+        !self.conditions.is_empty()
+    }
+
+    fn remove_violations(&self, outfits: Forest<Item>) -> Forest<Item> {
+        let met = meeting(&outfits, &self.conditions);
+
+        self.inclusions.iter()
+            .fold(outfits, |outfits, inclusion| outfits.difference(&met.clone().subset_none(inclusion)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum AttributeRelation {
+    Equal,
+    NotEqual,
+}
+
+/// An attribute rule with the attribute value of every item in both families resolved.
+/// An item without the attribute, on either side, breaks an `Equal` relation and never breaks a `NotEqual` one.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct AttributeRule {
+    relation: AttributeRelation,
+    left: BTreeMap<Item, Option<String>>,
+    right: BTreeMap<Item, Option<String>>,
+}
+
+impl AttributeRule {
+    pub fn new(relation: AttributeRelation, left: BTreeMap<Item, Option<String>>, right: BTreeMap<Item, Option<String>>) -> Self {
+        AttributeRule { relation, left, right }
+    }
+
+    fn breaks(&self, left: &Option<String>, right: &Option<String>) -> bool {
+        match (self.relation, left, right) {
+            (AttributeRelation::Equal, Some(left), Some(right)) => left != right,
+            (AttributeRelation::Equal, _, _) => true,
+            (AttributeRelation::NotEqual, Some(left), Some(right)) => left == right,
+            (AttributeRelation::NotEqual, _, _) => false,
+        }
+    }
+}
+
+impl CatalogRule for AttributeRule {
+    fn check(&self, outfit: &[Item]) -> bool {
+        let left = outfit.iter().filter_map(|item| self.left.get(item)).next();
+        let right = outfit.iter().filter_map(|item| self.right.get(item)).next();
+
+        match (left, right) {
+            (Some(left), Some(right)) => self.breaks(left, right),
+            _ => false,
+        }
+    }
+
+    fn has_conditions(&self) -> bool {
+        true
+    }
+
+    /// Removes, for each item on the left, the outfits pairing it with a right item it breaks the relation with.
+    fn remove_violations(&self, outfits: Forest<Item>) -> Forest<Item> {
+        self.left.iter().fold(outfits, |outfits, (left, left_value)| {
+            let broken_with = self.right.iter()
+                .filter(|&(_, right_value)| self.breaks(left_value, right_value))
+                .map(|(right, _)| right.clone())
+                .collect::<Vec<_>>();
+            if broken_with.is_empty() {
+                return outfits;
+            }
+
+            let broken = with_any(&outfits.clone().subset_all(&[left.clone()]), &broken_with);
+            outfits.difference(&broken)
+        })
+    }
+}
+
+/// The rules a catalog was built with, kept so outfits from outside the catalog can be checked against them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CatalogRules {
+    exclusions: Vec<ExclusionRule>,
+    inclusions: Vec<InclusionRule>,
+    attribute_rules: Vec<AttributeRule>,
+    constraints: Vec<AggregateConstraint>,
+}
+
+impl CatalogRules {
+    pub fn new(exclusions: Vec<ExclusionRule>, inclusions: Vec<InclusionRule>) -> Self {
+        CatalogRules { exclusions, inclusions, attribute_rules: vec![], constraints: vec![] }
+    }
+
+    pub fn with_attribute_rules(self, attribute_rules: Vec<AttributeRule>) -> Self {
+        CatalogRules { attribute_rules, ..self }
+    }
+
+    pub fn with_constraints(self, constraints: Vec<AggregateConstraint>) -> Self {
+        CatalogRules { constraints, ..self }
+    }
+
+    pub fn exclusions(&self) -> &[ExclusionRule] {
+        &self.exclusions
+    }
+
+    pub fn inclusions(&self) -> &[InclusionRule] {
+        &self.inclusions
+    }
+
+    pub fn attribute_rules(&self) -> &[AttributeRule] {
+        &self.attribute_rules
+    }
+
+    /// The aggregate rules the catalog was built with.
+    pub fn constraints(&self) -> &[AggregateConstraint] {
+        &self.constraints
+    }
+}
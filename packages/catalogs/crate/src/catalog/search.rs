@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use weave::zdd2::Forest;
+
+use types::{Family, Item};
+
+use super::Catalog;
+
+/// An item, or none, picked for each family before `next`, and the outfits that agree with them.
+/// `bound` is the cost of the picks plus the cheapest item left for every family still to pick.
+struct Candidate {
+    bound: f64,
+    cost: f64,
+    picks: Vec<Option<Item>>,
+    next: usize,
+    forest: Forest<Item>,
+}
+
+/// `BinaryHeap` pops the greatest candidate, so the lowest bound is the greatest.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.bound.partial_cmp(&self.bound)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.picks.cmp(&self.picks))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Catalog {
+    /// The `limit` outfits of `forest` with the lowest total cost, cheapest first, with their items sorted and their costs.
+    /// Outfits of equal cost are ordered by their items family by family, an outfit without a family's item coming first.
+    ///
+    /// This is a best-first search that picks an item for one family at a time, restricting the forest to the outfits with it,
+    /// so only the partial outfits that could still be among the cheapest are ever looked at.
+    pub(super) fn cheapest<C: Fn(&Item) -> f64>(&self, forest: Forest<Item>, cost: C, limit: usize) -> Vec<(Vec<Item>, f64)> {
+        if limit == 0 || forest.len() == 0 {
+            return vec![];
+        }
+
+        let mut families: BTreeMap<&Family, Vec<&Item>> = BTreeMap::new();
+        for (item, family) in &self.items {
+            families.entry(family).or_insert_with(Vec::new).push(item);
+        }
+        let families = families.into_iter()
+            .map(|(_, mut items)| {
+                items.sort();
+                items
+            })
+            .collect::<Vec<_>>();
+        let costs: HashMap<&Item, f64> = self.items.keys()
+            .map(|item| (item, cost(item)))
+            .collect();
+
+        // The cheapest choice left in every family from `next` on. A family some outfits have no item from can cost nothing.
+        let remaining = |forest: &Forest<Item>, next: usize| -> f64 {
+            let occurrences: HashMap<Item, usize> = forest.occurrences().into_iter().collect();
+
+            families[next..].iter()
+                .map(|items| {
+                    let live = items.iter()
+                        .filter_map(|item| occurrences.get(*item).map(|&count| (costs[item], count)));
+                    let (cheapest, covered) = live.fold((None, 0), |(cheapest, covered): (Option<f64>, usize), (cost, count)| {
+                        (Some(cheapest.map_or(cost, |cheapest| cheapest.min(cost))), covered + count)
+                    });
+
+                    match cheapest {
+                        Some(cheapest) if covered < forest.len() => cheapest.min(0.0),
+                        Some(cheapest) => cheapest,
+                        None => 0.0,
+                    }
+                })
+                .sum()
+        };
+
+        let mut candidates = BinaryHeap::new();
+        candidates.push(Candidate { bound: remaining(&forest, 0), cost: 0.0, picks: vec![], next: 0, forest });
+
+        // Candidates of equal bound come out in pick order, so complete outfits do too.
+        let mut found: Vec<(Vec<Item>, f64)> = vec![];
+        while let Some(candidate) = candidates.pop() {
+            if candidate.next == families.len() {
+                let mut outfit = candidate.picks.into_iter().flatten().collect::<Vec<_>>();
+                outfit.sort();
+                found.push((outfit, candidate.cost));
+
+                if found.len() == limit {
+                    break;
+                }
+                continue;
+            }
+
+            let items = &families[candidate.next];
+            let next = candidate.next + 1;
+            let occurrences: HashMap<Item, usize> = candidate.forest.occurrences().into_iter().collect();
+
+            let mut covered = 0;
+            for &item in items {
+                let count = occurrences.get(item).cloned().unwrap_or(0);
+                if count == 0 {
+                    continue;
+                }
+                covered += count;
+
+                let forest = candidate.forest.clone().subset_all(&[item.clone()]);
+                let cost = candidate.cost + costs[item];
+                let mut picks = candidate.picks.clone();
+                picks.push(Some(item.clone()));
+
+                candidates.push(Candidate { bound: cost + remaining(&forest, next), cost, picks, next, forest });
+            }
+
+            if covered < candidate.forest.len() {
+                let items = items.iter().map(|&item| item.clone()).collect::<Vec<_>>();
+                let forest = candidate.forest.subset_none(&items);
+                let cost = candidate.cost;
+                let mut picks = candidate.picks;
+                picks.push(None);
+
+                candidates.push(Candidate { bound: cost + remaining(&forest, next), cost, picks, next, forest });
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{catalog, items};
+    use types::Item;
+
+    fn cost(item: &Item) -> f64 {
+        match item.as_str() {
+            "shirts:blue" => 1.0,
+            "shirts:red" => 2.0,
+            "pants:jeans" => 3.0,
+            "pants:slacks" => 1.0,
+            _ => 10.0,
+        }
+    }
+
+    #[test]
+    fn outfits_come_out_cheapest_first() {
+        let catalog = catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans", "shirts:red"],
+            &["pants:slacks", "shirts:red"],
+        ]);
+
+        let outfits = catalog.cheapest(catalog.combinations.clone(), cost, 5);
+
+        assert_eq!(
+            vec![
+                (items(&["pants:slacks", "shirts:red"]), 3.0),
+                (items(&["pants:jeans", "shirts:blue"]), 4.0),
+                (items(&["pants:jeans", "shirts:red"]), 5.0),
+            ],
+            outfits
+        );
+    }
+
+    #[test]
+    fn ties_come_out_family_by_family() {
+        let catalog = catalog(&[
+            &["pants:slacks", "shirts:red"],
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans", "shirts:red"],
+        ]);
+
+        let outfits = catalog.cheapest(catalog.combinations.clone(), |_| 1.0, 2);
+
+        assert_eq!(
+            vec![
+                (items(&["pants:jeans", "shirts:blue"]), 2.0),
+                (items(&["pants:jeans", "shirts:red"]), 2.0),
+            ],
+            outfits
+        );
+    }
+
+    #[test]
+    fn families_missing_from_some_outfits_cost_nothing() {
+        let catalog = catalog(&[
+            &["pants:jeans", "shirts:blue"],
+            &["pants:jeans"],
+            &["shoes:boots"],
+        ]);
+
+        let outfits = catalog.cheapest(catalog.combinations.clone(), cost, 2);
+
+        assert_eq!(
+            vec![
+                (items(&["pants:jeans"]), 3.0),
+                (items(&["pants:jeans", "shirts:blue"]), 4.0),
+            ],
+            outfits
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use types::{Family, Item};
+
+use super::{AggregateConstraint, AttributeRule, Catalog, CatalogRule, ExclusionRule, InclusionRule};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RuleViolation<R> {
+    index: usize,
+    rule: R,
+}
+
+/// `violated_constraints` are the aggregate rules the catalog was built with,
+/// `violated_query_constraints` the constraints of the state the outfit was checked in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutfitValidation {
+    valid: bool,
+    unknown_items: Vec<Item>,
+    missing_families: Vec<Family>,
+    duplicate_families: Vec<Family>,
+    violated_exclusions: Vec<RuleViolation<ExclusionRule>>,
+    violated_inclusions: Vec<RuleViolation<InclusionRule>>,
+    violated_attribute_rules: Vec<RuleViolation<AttributeRule>>,
+    violated_constraints: Vec<RuleViolation<AggregateConstraint>>,
+    violated_query_constraints: Vec<RuleViolation<AggregateConstraint>>,
+}
+
+impl OutfitValidation {
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+}
+
+impl Catalog {
+    /// Checks a complete outfit against the catalog and the given query-time constraints.
+    /// Rule violations are reported with the position of the rule in the assembly, or in `constraints`.
+    pub fn validate_outfit(&self, outfit: &[Item], constraints: &[AggregateConstraint]) -> OutfitValidation {
+        let outfit = outfit.iter().cloned().unique().sorted().collect::<Vec<_>>();
+
+        let unknown_items = self.not_recognized(&outfit);
+
+        let mut picks: BTreeMap<&Family, usize> = self.items.values().map(|family| (family, 0)).collect();
+        for family in outfit.iter().filter_map(|item| self.items.get(item)) {
+            *picks.entry(family).or_insert(0) += 1;
+        }
+        let missing_families = picks.iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(family, _)| (*family).clone())
+            .collect::<Vec<_>>();
+        let duplicate_families = picks.iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(family, _)| (*family).clone())
+            .collect::<Vec<_>>();
+
+        let violated_exclusions = violations(self.rules.exclusions(), &outfit);
+        let violated_inclusions = violations(self.rules.inclusions(), &outfit);
+        let violated_attribute_rules = violations(self.rules.attribute_rules(), &outfit);
+        let violated_constraints = self.constraint_violations(self.rules.constraints(), &outfit);
+        let violated_query_constraints = self.constraint_violations(constraints, &outfit);
+
+        // Every outfit in the catalog has exactly one item per family, so a complete outfit is either in it or not.
+        let valid = unknown_items.is_empty()
+            && missing_families.is_empty()
+            && duplicate_families.is_empty()
+            && violated_query_constraints.is_empty()
+            && self.combinations.clone().subset_all(&outfit).len() == 1;
+
+        OutfitValidation {
+            valid,
+            unknown_items,
+            missing_families,
+            duplicate_families,
+            violated_exclusions,
+            violated_inclusions,
+            violated_attribute_rules,
+            violated_constraints,
+            violated_query_constraints,
+        }
+    }
+
+    fn constraint_violations(&self, constraints: &[AggregateConstraint], outfit: &[Item]) -> Vec<RuleViolation<AggregateConstraint>> {
+        constraints.iter()
+            .enumerate()
+            .filter(|(_, constraint)| constraint.violated(outfit, &self.items, &self.numeric_attributes))
+            .map(|(index, constraint)| RuleViolation { index, rule: constraint.clone() })
+            .collect()
+    }
+}
+
+fn violations<R: CatalogRule + Clone>(rules: &[R], outfit: &[Item]) -> Vec<RuleViolation<R>> {
+    rules.iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.check(outfit))
+        .map(|(index, rule)| RuleViolation { index, rule: rule.clone() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::fixtures::{self, items, priced};
+    use catalog::{Aggregate, AggregateConstraint, AttributeRelation, AttributeRule, Bound, CatalogRules, CatalogState, Comparison};
+    use types::Item;
+
+    use super::{Catalog, ExclusionRule, InclusionRule, RuleViolation};
+
+    fn catalog() -> Catalog {
+        let rules = CatalogRules::new(
+            vec![ExclusionRule::new(vec![vec![Item::from("shirts:red")]], vec![Item::from("pants:jeans")])],
+            vec![InclusionRule::new(vec![vec![Item::from("shirts:blue")]], vec![vec![Item::from("pants:jeans")]])],
+        );
+
+        fixtures::catalog(&[&["pants:jeans", "shirts:blue"], &["pants:slacks", "shirts:red"]]).with_rules(rules)
+    }
+
+    #[test]
+    fn outfit_in_the_catalog_is_valid() {
+        let validation = catalog().validate_outfit(&[Item::from("shirts:blue"), Item::from("pants:jeans")], &[]);
+
+        assert!(validation.is_valid());
+        assert!(validation.violated_exclusions.is_empty());
+        assert!(validation.violated_inclusions.is_empty());
+    }
+
+    #[test]
+    fn outfit_reports_unknown_items_and_family_picks() {
+        let validation = catalog().validate_outfit(&[
+            Item::from("shirts:blue"),
+            Item::from("shirts:red"),
+            Item::from("shoes:boots"),
+        ], &[]);
+
+        assert!(!validation.is_valid());
+        assert_eq!(vec![Item::from("shoes:boots")], validation.unknown_items);
+        assert_eq!(vec![String::from("pants")], validation.missing_families);
+        assert_eq!(vec![String::from("shirts")], validation.duplicate_families);
+    }
+
+    #[test]
+    fn outfit_reports_broken_rules() {
+        let validation = catalog().validate_outfit(&[Item::from("shirts:red"), Item::from("pants:jeans")], &[]);
+
+        assert!(!validation.is_valid());
+        assert_eq!(vec![0], indices(&validation.violated_exclusions));
+        assert!(validation.violated_inclusions.is_empty());
+
+        let validation = catalog().validate_outfit(&[Item::from("shirts:blue"), Item::from("pants:slacks")], &[]);
+
+        assert!(!validation.is_valid());
+        assert!(validation.violated_exclusions.is_empty());
+        assert_eq!(vec![0], indices(&validation.violated_inclusions));
+    }
+
+    fn budget(limit: f64) -> AggregateConstraint {
+        AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(limit), vec![])
+    }
+
+    #[test]
+    fn outfit_reports_broken_attribute_rules_and_build_constraints() {
+        let matching_colors = AttributeRule::new(
+            AttributeRelation::Equal,
+            btreemap! { Item::from("shirts:blue") => Some("blue".into()), Item::from("shirts:red") => Some("red".into()) },
+            btreemap! { Item::from("pants:jeans") => Some("blue".into()), Item::from("pants:slacks") => None },
+        );
+        let rules = CatalogRules::new(vec![], vec![])
+            .with_attribute_rules(vec![matching_colors])
+            .with_constraints(vec![budget(100.0)]);
+        let catalog = priced(fixtures::catalog(&[&["pants:jeans", "shirts:blue"]])).with_rules(rules);
+
+        let validation = catalog.validate_outfit(&items(&["shirts:red", "pants:slacks"]), &[]);
+
+        assert!(!validation.is_valid());
+        assert_eq!(vec![0], indices(&validation.violated_attribute_rules));
+        assert_eq!(vec![0], indices(&validation.violated_constraints));
+        assert!(validation.violated_query_constraints.is_empty());
+
+        let validation = catalog.validate_outfit(&items(&["shirts:blue", "pants:jeans"]), &[]);
+
+        assert!(validation.is_valid());
+        assert!(validation.violated_attribute_rules.is_empty());
+        assert!(validation.violated_constraints.is_empty());
+    }
+
+    #[test]
+    fn outfit_reports_broken_query_constraints() {
+        let state = CatalogState::from_catalog(priced(fixtures::shirts_and_pants()))
+            .with_constraints(vec![budget(100.0), budget(60.0)]);
+
+        let validation = state.validate_outfit(&items(&["shirts:blue", "pants:jeans"]))
+            .expect("expected outfit to be validated");
+
+        assert!(!validation.is_valid());
+        assert!(validation.violated_constraints.is_empty());
+        assert_eq!(vec![1], indices(&validation.violated_query_constraints));
+    }
+
+    fn indices<R>(violations: &[RuleViolation<R>]) -> Vec<usize> {
+        violations.iter().map(|violation| violation.index).collect()
+    }
+}
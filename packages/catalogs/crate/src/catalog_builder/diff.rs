@@ -0,0 +1,99 @@
+use catalog::{AggregateConstraint, CatalogDiff, RuleChanges};
+
+use super::{build_catalog, CatalogAssembly, CatalogAttributeRule, CatalogBuilderError, CatalogExclusionRule, CatalogInclusionRule};
+
+/// A `CatalogDiff` of the built catalogs, along with the rules that changed as they were written.
+/// Source rules can change without changing the built catalog, e.g. when an attribute rule is rewritten as item rules.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AssemblyDiff {
+    catalog: CatalogDiff,
+    exclusion_rules: RuleChanges<CatalogExclusionRule>,
+    inclusion_rules: RuleChanges<CatalogInclusionRule>,
+    attribute_rules: RuleChanges<CatalogAttributeRule>,
+    aggregate_rules: RuleChanges<AggregateConstraint>,
+}
+
+impl AssemblyDiff {
+    pub fn catalog(&self) -> &CatalogDiff {
+        &self.catalog
+    }
+
+    pub fn exclusion_rules(&self) -> &RuleChanges<CatalogExclusionRule> {
+        &self.exclusion_rules
+    }
+
+    pub fn inclusion_rules(&self) -> &RuleChanges<CatalogInclusionRule> {
+        &self.inclusion_rules
+    }
+
+    pub fn attribute_rules(&self) -> &RuleChanges<CatalogAttributeRule> {
+        &self.attribute_rules
+    }
+
+    pub fn aggregate_rules(&self) -> &RuleChanges<AggregateConstraint> {
+        &self.aggregate_rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.catalog.is_empty()
+            && self.exclusion_rules.is_empty()
+            && self.inclusion_rules.is_empty()
+            && self.attribute_rules.is_empty()
+            && self.aggregate_rules.is_empty()
+    }
+}
+
+pub fn diff_assemblies(before: &CatalogAssembly, after: &CatalogAssembly) -> Result<AssemblyDiff, CatalogBuilderError> {
+    let catalog = build_catalog(before.clone())?
+        .diff(&build_catalog(after.clone())?);
+
+    Ok(AssemblyDiff {
+        catalog,
+        exclusion_rules: RuleChanges::between(&before.exclusions, &after.exclusions),
+        inclusion_rules: RuleChanges::between(&before.inclusions, &after.inclusions),
+        attribute_rules: RuleChanges::between(&before.attribute_rules, &after.attribute_rules),
+        aggregate_rules: RuleChanges::between(&before.aggregate_rules, &after.aggregate_rules),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use types::Item;
+
+    use super::super::{CatalogAssembly, CatalogExclusionRule, RuleTarget};
+    use super::diff_assemblies;
+
+    fn assembly(exclusions: Vec<CatalogExclusionRule>) -> CatalogAssembly {
+        CatalogAssembly::new(
+            btreemap! {
+                "shirts".into() => vec!["shirts:red".into(), "shirts:blue".into()],
+                "pants".into() => vec!["pants:jeans".into(), "pants:slacks".into()],
+            },
+            exclusions,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn unchanged_assemblies_have_an_empty_diff() {
+        assert!(diff_assemblies(&assembly(vec![]), &assembly(vec![])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn diff_reports_source_rules_and_lost_outfits() {
+        let exclusion = CatalogExclusionRule::new(
+            vec![RuleTarget::Item("shirts:red".into())],
+            vec![RuleTarget::Item("pants:jeans".into())],
+        );
+
+        let diff = diff_assemblies(&assembly(vec![]), &assembly(vec![exclusion.clone()])).unwrap();
+
+        assert_eq!(&[exclusion], diff.exclusion_rules().added());
+        assert_eq!(1, diff.catalog().exclusions().added().len());
+        assert_eq!(
+            &[vec![Item::from("pants:jeans"), Item::from("shirts:red")]],
+            diff.catalog().lost_outfits()
+        );
+        assert!(diff.catalog().gained_outfits().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use catalog::Catalog;
+use types::{Family, Item};
+
+use super::{build_catalog, CatalogAssembly, CatalogBuilderError, CatalogExclusionRule, CatalogInclusionRule, RuleTarget};
+
+/// A single change to an assembly. Rules are removed by their index in the assembly.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum AssemblyEdit {
+    AddExclusion { rule: CatalogExclusionRule },
+    RemoveExclusion { index: usize },
+    AddInclusion { rule: CatalogInclusionRule },
+    RemoveInclusion { index: usize },
+    AddItem { family: Family, item: Item },
+    RemoveItem { item: Item },
+}
+
+/// What an edit would do to the built catalog.
+/// `dead_items` and `emptied_families` only name items and families the edit leaves without outfits.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct EditImpact {
+    outfits_before: usize,
+    outfits_after: usize,
+    removed_outfits: usize,
+    added_outfits: usize,
+    dead_items: Vec<Item>,
+    emptied_families: Vec<Family>,
+}
+
+impl EditImpact {
+    pub fn outfits_before(&self) -> usize {
+        self.outfits_before
+    }
+
+    pub fn outfits_after(&self) -> usize {
+        self.outfits_after
+    }
+
+    pub fn removed_outfits(&self) -> usize {
+        self.removed_outfits
+    }
+
+    pub fn added_outfits(&self) -> usize {
+        self.added_outfits
+    }
+
+    pub fn dead_items(&self) -> &[Item] {
+        &self.dead_items
+    }
+
+    pub fn emptied_families(&self) -> &[Family] {
+        &self.emptied_families
+    }
+}
+
+impl CatalogAssembly {
+    /// Applies `edit` to a copy of the assembly.
+    /// Removing an item also removes it from rules, and drops rules it was the only condition or target of,
+    /// as well as its family when it was the family's last item.
+    pub fn edit(&self, edit: &AssemblyEdit) -> Result<CatalogAssembly, CatalogBuilderError> {
+        let mut assembly = self.clone();
+
+        match edit {
+            AssemblyEdit::AddExclusion { rule } => assembly.exclusions.push(rule.clone()),
+            AssemblyEdit::RemoveExclusion { index } => {
+                if *index >= assembly.exclusions.len() {
+                    return Err(CatalogBuilderError::UnknownExclusionRule { index: *index });
+                }
+                assembly.exclusions.remove(*index);
+            }
+            AssemblyEdit::AddInclusion { rule } => assembly.inclusions.push(rule.clone()),
+            AssemblyEdit::RemoveInclusion { index } => {
+                if *index >= assembly.inclusions.len() {
+                    return Err(CatalogBuilderError::UnknownInclusionRule { index: *index });
+                }
+                assembly.inclusions.remove(*index);
+            }
+            AssemblyEdit::AddItem { family, item } => assembly.families
+                .entry(family.clone())
+                .or_insert_with(Vec::new)
+                .push(item.clone()),
+            AssemblyEdit::RemoveItem { item } => {
+                let family = assembly.families.iter()
+                    .find(|(_, items)| items.contains(item))
+                    .map(|(family, _)| family.clone())
+                    .ok_or_else(|| CatalogBuilderError::UnknownItem { item: item.clone() })?;
+
+                assembly.families.get_mut(&family).unwrap().retain(|other| other != item);
+                if assembly.families[&family].is_empty() {
+                    assembly.families.remove(&family);
+                }
+                assembly.exclusions = assembly.exclusions.iter()
+                    .filter_map(|rule| {
+                        let conditions = without_item(&rule.conditions, item)?;
+                        let exclusions = without_item(&rule.exclusions, item)?;
+                        Some(CatalogExclusionRule { conditions, exclusions })
+                    })
+                    .collect();
+                assembly.inclusions = assembly.inclusions.iter()
+                    .filter_map(|rule| {
+                        let conditions = without_item(&rule.conditions, item)?;
+                        let inclusions = without_item(&rule.inclusions, item)?;
+                        Some(CatalogInclusionRule { conditions, inclusions })
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(assembly)
+    }
+}
+
+/// Builds the catalog with and without `edit`, and reports the difference without keeping either.
+pub fn dry_run(assembly: &CatalogAssembly, edit: &AssemblyEdit) -> Result<EditImpact, CatalogBuilderError> {
+    let before = build_catalog(assembly.clone())?;
+    let after = build_catalog(assembly.edit(edit)?)?;
+
+    let (added_outfits, removed_outfits) = before.outfits_gained_and_lost(&after);
+    let occurrences_before = occurrences(&before);
+    let occurrences_after = occurrences(&after);
+
+    let is_dead = |occurrences: &BTreeMap<Item, (Family, usize)>, item: &Item| {
+        occurrences.get(item).map_or(false, |&(_, count)| count == 0)
+    };
+    let dead_items = occurrences_after.keys()
+        .filter(|&item| is_dead(&occurrences_after, item) && !is_dead(&occurrences_before, item))
+        .cloned()
+        .collect();
+
+    let empty_families = |occurrences: &BTreeMap<Item, (Family, usize)>| {
+        let live = occurrences.values()
+            .filter(|&&(_, count)| count > 0)
+            .map(|(family, _)| family)
+            .collect::<BTreeSet<_>>();
+
+        occurrences.values()
+            .map(|(family, _)| family.clone())
+            .filter(|family| !live.contains(family))
+            .collect::<BTreeSet<_>>()
+    };
+    let emptied_before = empty_families(&occurrences_before);
+    let emptied_families = empty_families(&occurrences_after)
+        .into_iter()
+        .filter(|family| !emptied_before.contains(family))
+        .collect();
+
+    Ok(EditImpact {
+        outfits_before: before.outfit_count(),
+        outfits_after: after.outfit_count(),
+        removed_outfits,
+        added_outfits,
+        dead_items,
+        emptied_families,
+    })
+}
+
+fn occurrences(catalog: &Catalog) -> BTreeMap<Item, (Family, usize)> {
+    catalog.item_occurrences()
+        .map(|(family, (item, count))| (item, (family, count)))
+        .collect()
+}
+
+/// The targets without `item`, or `None` when `item` was the only target.
+fn without_item(targets: &[RuleTarget], item: &Item) -> Option<Vec<RuleTarget>> {
+    let remaining = targets.iter()
+        .filter(|target| **target != RuleTarget::Item(item.clone()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if remaining.is_empty() && !targets.is_empty() {
+        None
+    } else {
+        Some(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{Family, Item};
+
+    use super::super::{CatalogAssembly, CatalogBuilderError, CatalogExclusionRule, RuleTarget};
+    use super::{dry_run, AssemblyEdit};
+
+    fn assembly() -> CatalogAssembly {
+        CatalogAssembly::new(
+            btreemap! {
+                "shirts".into() => vec!["shirts:red".into(), "shirts:blue".into()],
+                "pants".into() => vec!["pants:jeans".into(), "pants:slacks".into()],
+            },
+            vec![CatalogExclusionRule::new(
+                vec![RuleTarget::Item("shirts:red".into())],
+                vec![RuleTarget::Item("pants:jeans".into())],
+            )],
+            vec![],
+        )
+    }
+
+    fn exclusion(condition: &str, exclusions: &[&str]) -> CatalogExclusionRule {
+        CatalogExclusionRule::new(
+            vec![RuleTarget::Item(condition.into())],
+            exclusions.iter().map(|&item| RuleTarget::Item(item.into())).collect(),
+        )
+    }
+
+    #[test]
+    fn adding_an_exclusion_reports_removed_outfits_and_dead_items() {
+        let edit = AssemblyEdit::AddExclusion { rule: exclusion("shirts:red", &["pants:slacks"]) };
+
+        let impact = dry_run(&assembly(), &edit).unwrap();
+
+        assert_eq!(3, impact.outfits_before());
+        assert_eq!(2, impact.outfits_after());
+        assert_eq!(1, impact.removed_outfits());
+        assert_eq!(0, impact.added_outfits());
+        assert_eq!(&[Item::from("shirts:red")], impact.dead_items());
+        assert!(impact.emptied_families().is_empty());
+    }
+
+    #[test]
+    fn removing_an_exclusion_reports_added_outfits() {
+        let impact = dry_run(&assembly(), &AssemblyEdit::RemoveExclusion { index: 0 }).unwrap();
+
+        assert_eq!(4, impact.outfits_after());
+        assert_eq!(1, impact.added_outfits());
+        assert!(impact.dead_items().is_empty());
+    }
+
+    #[test]
+    fn removing_an_item_drops_the_rules_it_was_the_only_target_of() {
+        let impact = dry_run(&assembly(), &AssemblyEdit::RemoveItem { item: "pants:jeans".into() }).unwrap();
+
+        assert_eq!(2, impact.outfits_after());
+        assert_eq!(1, impact.removed_outfits());
+        assert_eq!(0, impact.added_outfits());
+    }
+
+    #[test]
+    fn excluding_every_outfit_empties_every_family() {
+        let edit = AssemblyEdit::AddExclusion { rule: exclusion("shirts:blue", &["pants:jeans", "pants:slacks"]) };
+        let assembly = assembly().edit(&edit).unwrap();
+
+        let edit = AssemblyEdit::AddExclusion { rule: exclusion("shirts:red", &["pants:slacks"]) };
+        let impact = dry_run(&assembly, &edit).unwrap();
+
+        assert_eq!(0, impact.outfits_after());
+        assert_eq!(&[Item::from("pants:slacks"), Item::from("shirts:red")], impact.dead_items());
+        assert_eq!(&[Family::from("pants"), Family::from("shirts")], impact.emptied_families());
+    }
+
+    #[test]
+    fn edits_must_name_existing_rules_and_items() {
+        assert_eq!(
+            CatalogBuilderError::UnknownExclusionRule { index: 3 },
+            dry_run(&assembly(), &AssemblyEdit::RemoveExclusion { index: 3 }).unwrap_err()
+        );
+        assert_eq!(
+            CatalogBuilderError::UnknownItem { item: "shoes:boots".into() },
+            dry_run(&assembly(), &AssemblyEdit::RemoveItem { item: "shoes:boots".into() }).unwrap_err()
+        );
+    }
+}
@@ -4,61 +4,201 @@ use std::str;
 use reduce::Reduce;
 use weave::zdd2::Forest;
 
-use catalog::Catalog;
-use types::{Family, Item};
-
-use self::validation::{CatalogBuilderError, validate_catalog};
-
+use catalog::{AggregateConstraint, AttributeRule, Catalog, CatalogRule, CatalogRules, ExclusionRule, InclusionRule};
+use types::{Attributes, Family, Item, NumericAttributes};
+
+pub use catalog::AttributeRelation;
+pub use self::diff::{diff_assemblies, AssemblyDiff};
+pub use self::impact::{dry_run, AssemblyEdit, EditImpact};
+pub use self::report::{RuleEffect, RuleId, RuleReport};
+use self::report::RuleTally;
+pub use self::validation::CatalogBuilderError;
+use self::validation::validate_catalog;
+
+mod diff;
+mod impact;
+mod report;
 mod validation;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CatalogAssembly {
     families: BTreeMap<Family, Vec<Item>>,
     exclusions: Vec<CatalogExclusionRule>,
     inclusions: Vec<CatalogInclusionRule>,
+    #[serde(default)]
+    attributes: BTreeMap<Item, Attributes>,
+    #[serde(default)]
+    attribute_rules: Vec<CatalogAttributeRule>,
+    #[serde(default)]
+    numeric_attributes: BTreeMap<Item, NumericAttributes>,
+    #[serde(default)]
+    aggregate_rules: Vec<AggregateConstraint>,
 }
 
-trait CatalogRule {
-    fn check(&self, outfit: &[Item]) -> bool;
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum RuleTarget {
+    Item(Item),
+    Attribute { attribute: String, value: String },
+}
+
+impl CatalogAssembly {
+    pub fn new(families: BTreeMap<Family, Vec<Item>>, exclusions: Vec<CatalogExclusionRule>, inclusions: Vec<CatalogInclusionRule>) -> Self {
+        CatalogAssembly { families, exclusions, inclusions, ..CatalogAssembly::default() }
+    }
+
+    pub fn families(&self) -> &BTreeMap<Family, Vec<Item>> {
+        &self.families
+    }
 
-    fn has_conditions(&self) -> bool;
+    pub fn exclusions(&self) -> &[CatalogExclusionRule] {
+        &self.exclusions
+    }
+
+    pub fn inclusions(&self) -> &[CatalogInclusionRule] {
+        &self.inclusions
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+impl RuleTarget {
+    fn expand(&self, attributes: &BTreeMap<Item, Attributes>) -> Vec<Item> {
+        match self {
+            RuleTarget::Item(item) => vec![item.clone()],
+            RuleTarget::Attribute { attribute, value } => attributes.iter()
+                .filter(|(_, item_attributes)| item_attributes.get(attribute) == Some(value))
+                .map(|(item, _)| item.clone())
+                .collect(),
+        }
+    }
+}
+
+impl From<Item> for RuleTarget {
+    fn from(item: Item) -> Self {
+        RuleTarget::Item(item)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct CatalogExclusionRule {
-    conditions: Vec<Item>,
-    exclusions: Vec<Item>,
+    conditions: Vec<RuleTarget>,
+    exclusions: Vec<RuleTarget>,
 }
 
-impl CatalogRule for CatalogExclusionRule {
-    fn check(&self, outfit: &[Item]) -> bool {
-        self.conditions.iter().all(|condition| outfit.contains(condition))
-            && self.exclusions.iter().any(|item| outfit.contains(item))
+impl CatalogExclusionRule {
+    pub fn new(conditions: Vec<RuleTarget>, exclusions: Vec<RuleTarget>) -> Self {
+        CatalogExclusionRule { conditions, exclusions }
+    }
+
+    pub fn conditions(&self) -> &[RuleTarget] {
+        &self.conditions
     }
 
-    fn has_conditions(&self) -> bool {
-        !self.conditions.is_empty()
+    pub fn exclusions(&self) -> &[RuleTarget] {
+        &self.exclusions
+    }
+
+    fn expand(&self, attributes: &BTreeMap<Item, Attributes>) -> ExclusionRule {
+        ExclusionRule::new(
+            expand_conditions(&self.conditions, attributes),
+            self.exclusions.iter()
+                .flat_map(|target| target.expand(attributes))
+                .collect(),
+        )
+    }
+
+    fn targets(&self) -> impl Iterator<Item=&RuleTarget> {
+        self.conditions.iter().chain(self.exclusions.iter())
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct CatalogInclusionRule {
-    conditions: Vec<Item>,
-    inclusions: Vec<Item>,
+    conditions: Vec<RuleTarget>,
+    inclusions: Vec<RuleTarget>,
 }
 
-impl CatalogRule for CatalogInclusionRule {
-    fn check(&self, outfit: &[Item]) -> bool {
-        self.conditions.iter().all(|condition| outfit.contains(condition))
-            && self.inclusions.iter().any(|item| !outfit.contains(item))
+impl CatalogInclusionRule {
+    pub fn new(conditions: Vec<RuleTarget>, inclusions: Vec<RuleTarget>) -> Self {
+        CatalogInclusionRule { conditions, inclusions }
+    }
+
+    pub fn conditions(&self) -> &[RuleTarget] {
+        &self.conditions
+    }
+
+    pub fn inclusions(&self) -> &[RuleTarget] {
+        &self.inclusions
+    }
+
+    fn expand(&self, attributes: &BTreeMap<Item, Attributes>) -> InclusionRule {
+        InclusionRule::new(
+            expand_conditions(&self.conditions, attributes),
+            self.inclusions.iter()
+                .map(|target| target.expand(attributes))
+                .collect(),
+        )
     }
 
-    fn has_conditions(&self) -> bool {
-        !self.conditions.is_empty()
+    fn targets(&self) -> impl Iterator<Item=&RuleTarget> {
+        self.conditions.iter().chain(self.inclusions.iter())
     }
 }
 
-pub fn build_catalog(CatalogAssembly { families, exclusions, inclusions }: CatalogAssembly) -> Result<Catalog, CatalogBuilderError> {
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CatalogAttributeRule {
+    attribute: String,
+    families: (Family, Family),
+    relation: AttributeRelation,
+}
+
+impl CatalogAttributeRule {
+    fn expand(&self, families: &BTreeMap<Family, Vec<Item>>, attributes: &BTreeMap<Item, Attributes>) -> AttributeRule {
+        let values = |family: &Family| families.get(family)
+            .into_iter()
+            .flatten()
+            .map(|item| {
+                let value = attributes.get(item)
+                    .and_then(|item_attributes| item_attributes.get(&self.attribute))
+                    .cloned();
+
+                (item.clone(), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        AttributeRule::new(self.relation, values(&self.families.0), values(&self.families.1))
+    }
+}
+
+fn expand_conditions(conditions: &[RuleTarget], attributes: &BTreeMap<Item, Attributes>) -> Vec<Vec<Item>> {
+    conditions.iter()
+        .map(|target| target.expand(attributes))
+        .collect()
+}
+
+pub fn build_catalog(assembly: CatalogAssembly) -> Result<Catalog, CatalogBuilderError> {
+    build(assembly, None)
+}
+
+/// Builds the catalog along with a report of the outfits each exclusion and inclusion rule removes.
+/// Every rule is checked against every outfit, so this is slower than `build_catalog`.
+pub fn build_catalog_with_report(assembly: CatalogAssembly) -> Result<(Catalog, RuleReport), CatalogBuilderError> {
+    let mut tally = RuleTally::new(assembly.exclusions.len(), assembly.inclusions.len());
+    let catalog = build(assembly, Some(&mut tally))?;
+
+    Ok((catalog, tally.into_report()))
+}
+
+fn build(assembly: CatalogAssembly, tally: Option<&mut RuleTally>) -> Result<Catalog, CatalogBuilderError> {
+    let CatalogAssembly {
+        families,
+        exclusions,
+        inclusions,
+        attributes,
+        attribute_rules,
+        numeric_attributes,
+        aggregate_rules,
+    } = assembly;
+
     let item_index: HashMap<Item, Family> = families.iter()
         .flat_map(|(family, items)| items.iter()
             .map(|item| (item.clone(), family.clone()))
@@ -69,33 +209,73 @@ pub fn build_catalog(CatalogAssembly { families, exclusions, inclusions }: Catal
     validate_catalog(
         &families,
         &item_index,
+        &attributes,
         &exclusions,
         &inclusions,
+        &attribute_rules,
+        &numeric_attributes,
+        &aggregate_rules,
     )?;
 
+    let exclusions = exclusions.iter()
+        .map(|rule| rule.expand(&attributes))
+        .collect::<Vec<_>>();
+    let inclusions = inclusions.iter()
+        .map(|rule| rule.expand(&attributes))
+        .collect::<Vec<_>>();
+    let attribute_rules = attribute_rules.iter()
+        .map(|rule| rule.expand(&families, &attributes))
+        .collect::<Vec<_>>();
+
     let forest = families.into_iter()
         .map(|(_, items)| Forest::unique(&items))
         .reduce(Forest::product)
         .ok_or(validation::CatalogBuilderError::EmptyCatalog)?;
 
-    let combinations = forest.trees().into_iter()
-        .filter(|outfit| {
-            !exclusions.iter()
-                .any(|rule| rule.check(outfit))
-        })
-        .filter(|outfit| {
-            !inclusions.iter()
-                .any(|rule| rule.check(outfit))
-        })
-        .collect::<Vec<Vec<_>>>();
-
-    let combinations = Forest::many(&combinations);
-
-    Ok(Catalog::new(combinations, item_index))
+    let violates_aggregate = |outfit: &[Item]| aggregate_rules.iter().any(|rule| rule.violated(outfit, &item_index, &numeric_attributes));
+
+    let combinations = match tally {
+        Some(tally) => {
+            let combinations = forest.trees().into_iter()
+                .filter(|outfit| tally.record(
+                    exclusions.iter().map(|rule| rule.check(outfit))
+                        .chain(inclusions.iter().map(|rule| rule.check(outfit))),
+                    attribute_rules.iter().any(|rule| rule.check(outfit)) || violates_aggregate(outfit),
+                ))
+                .collect::<Vec<Vec<_>>>();
+
+            Forest::many(&combinations)
+        }
+        None => {
+            let forest = exclusions.iter().fold(forest, |forest, rule| rule.remove_violations(forest));
+            let forest = inclusions.iter().fold(forest, |forest, rule| rule.remove_violations(forest));
+            let forest = attribute_rules.iter().fold(forest, |forest, rule| rule.remove_violations(forest));
+
+            // Aggregate rules depend on the whole outfit, so only they are checked outfit by outfit.
+            if aggregate_rules.is_empty() {
+                forest
+            } else {
+                let combinations = forest.trees().into_iter()
+                    .filter(|outfit| !violates_aggregate(outfit))
+                    .collect::<Vec<Vec<_>>>();
+
+                Forest::many(&combinations)
+            }
+        }
+    };
+
+    let rules = CatalogRules::new(exclusions, inclusions)
+        .with_attribute_rules(attribute_rules)
+        .with_constraints(aggregate_rules);
+
+    Ok(Catalog::new(combinations, item_index)
+        .with_numeric_attributes(numeric_attributes)
+        .with_rules(rules))
 }
 
 #[cfg(test)]
 mod no_rules_tests {
+    use errors::{CatalogsError, StatusClass};
     use types::{Family, Item};
 
     use super::build_catalog;
@@ -108,6 +288,7 @@ mod no_rules_tests {
             families: btreemap! {},
             exclusions: vec![],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         }).unwrap_err();
 
         assert_eq!(
@@ -116,6 +297,23 @@ mod no_rules_tests {
         );
     }
 
+    #[test]
+    fn compound_errors_describe_every_error() {
+        let error = CatalogBuilderError::CompoundError {
+            errors: vec![
+                CatalogBuilderError::EmptyCatalog,
+                CatalogBuilderError::ExclusionMissingFamily { item: "blue".into() },
+            ]
+        };
+
+        assert_eq!("COMPOUND_ERROR", error.code());
+        assert_eq!(StatusClass::InvalidArgument, error.status());
+        assert_eq!(
+            "families are required to build a catalog; exclusion rule item 'blue' is not registered to a family",
+            error.to_string()
+        );
+    }
+
     #[test]
     fn two_families_cannot_share_items() {
         let blue = Item::from("blue");
@@ -130,6 +328,7 @@ mod no_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         }).unwrap_err();
 
         assert_eq!(
@@ -152,6 +351,7 @@ mod no_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -184,6 +384,7 @@ mod no_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -225,8 +426,9 @@ mod exclusion_rules_tests {
                 shirts => vec![red.clone(), blue.clone()],
                 pants => vec![jeans.clone(), slacks.clone()],
             },
-            exclusions: vec![CatalogExclusionRule { conditions: vec![red.clone()], exclusions: vec![jeans.clone()] }],
+            exclusions: vec![CatalogExclusionRule { conditions: vec![red.clone().into()], exclusions: vec![jeans.clone().into()] }],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -258,10 +460,11 @@ mod exclusion_rules_tests {
                 pants => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![red.clone()], exclusions: vec![jeans.clone()] },
-                CatalogExclusionRule { conditions: vec![blue.clone()], exclusions: vec![slacks.clone()] },
+                CatalogExclusionRule { conditions: vec![red.clone().into()], exclusions: vec![jeans.clone().into()] },
+                CatalogExclusionRule { conditions: vec![blue.clone().into()], exclusions: vec![slacks.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -292,10 +495,11 @@ mod exclusion_rules_tests {
                 pants => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![red.clone()], exclusions: vec![jeans.clone(), slacks.clone()] },
-                CatalogExclusionRule { conditions: vec![blue.clone()], exclusions: vec![jeans.clone(), slacks.clone()] },
+                CatalogExclusionRule { conditions: vec![red.clone().into()], exclusions: vec![jeans.clone().into(), slacks.clone().into()] },
+                CatalogExclusionRule { conditions: vec![blue.clone().into()], exclusions: vec![jeans.clone().into(), slacks.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -323,9 +527,10 @@ mod exclusion_rules_tests {
                 pants => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![blue.clone()], exclusions: vec![jeans.clone(), slacks.clone()] },
+                CatalogExclusionRule { conditions: vec![blue.clone().into()], exclusions: vec![jeans.clone().into(), slacks.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -356,9 +561,10 @@ mod exclusion_rules_tests {
                 pants => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![], exclusions: vec![jeans.clone()] },
+                CatalogExclusionRule { conditions: vec![], exclusions: vec![jeans.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -390,10 +596,11 @@ mod exclusion_rules_tests {
                 pants => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![black.clone()], exclusions: vec![jeans.clone()] },
-                CatalogExclusionRule { conditions: vec![jeans.clone()], exclusions: vec![black.clone()] },
+                CatalogExclusionRule { conditions: vec![black.clone().into()], exclusions: vec![jeans.clone().into()] },
+                CatalogExclusionRule { conditions: vec![jeans.clone().into()], exclusions: vec![black.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect_err("expected build to return Error");
 
@@ -420,10 +627,11 @@ mod exclusion_rules_tests {
                 pants.clone() => vec![jeans.clone(), slacks.clone()],
             },
             exclusions: vec![
-                CatalogExclusionRule { conditions: vec![jeans.clone()], exclusions: vec![slacks.clone()] },
-                CatalogExclusionRule { conditions: vec![slacks.clone()], exclusions: vec![jeans.clone()] },
+                CatalogExclusionRule { conditions: vec![jeans.clone().into()], exclusions: vec![slacks.clone().into()] },
+                CatalogExclusionRule { conditions: vec![slacks.clone().into()], exclusions: vec![jeans.clone().into()] },
             ],
             inclusions: vec![],
+            ..CatalogAssembly::default()
         })
             .expect_err("expected build to return Error");
 
@@ -461,8 +669,9 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![red.clone()], inclusions: vec![jeans.clone()] },
+                CatalogInclusionRule { conditions: vec![red.clone().into()], inclusions: vec![jeans.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -495,9 +704,10 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![red.clone()], inclusions: vec![jeans.clone()] },
-                CatalogInclusionRule { conditions: vec![blue.clone()], inclusions: vec![slacks.clone()] },
+                CatalogInclusionRule { conditions: vec![red.clone().into()], inclusions: vec![jeans.clone().into()] },
+                CatalogInclusionRule { conditions: vec![blue.clone().into()], inclusions: vec![slacks.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -529,9 +739,10 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![red.clone()], inclusions: vec![jeans.clone(), slacks.clone()] },
-                CatalogInclusionRule { conditions: vec![blue.clone()], inclusions: vec![jeans.clone(), slacks.clone()] },
+                CatalogInclusionRule { conditions: vec![red.clone().into()], inclusions: vec![jeans.clone().into(), slacks.clone().into()] },
+                CatalogInclusionRule { conditions: vec![blue.clone().into()], inclusions: vec![jeans.clone().into(), slacks.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -560,8 +771,9 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![red.clone()], inclusions: vec![jeans.clone(), slacks.clone()] },
+                CatalogInclusionRule { conditions: vec![red.clone().into()], inclusions: vec![jeans.clone().into(), slacks.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -593,8 +805,9 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![], inclusions: vec![jeans.clone()] },
+                CatalogInclusionRule { conditions: vec![], inclusions: vec![jeans.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect("expected build to return Catalog");
 
@@ -627,9 +840,10 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![black.clone()], inclusions: vec![jeans.clone()] },
-                CatalogInclusionRule { conditions: vec![jeans.clone()], inclusions: vec![black.clone()] },
+                CatalogInclusionRule { conditions: vec![black.clone().into()], inclusions: vec![jeans.clone().into()] },
+                CatalogInclusionRule { conditions: vec![jeans.clone().into()], inclusions: vec![black.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect_err("expected build to return Error");
 
@@ -657,9 +871,10 @@ mod inclusion_rules_tests {
             },
             exclusions: vec![],
             inclusions: vec![
-                CatalogInclusionRule { conditions: vec![jeans.clone()], inclusions: vec![slacks.clone()] },
-                CatalogInclusionRule { conditions: vec![slacks.clone()], inclusions: vec![jeans.clone()] },
+                CatalogInclusionRule { conditions: vec![jeans.clone().into()], inclusions: vec![slacks.clone().into()] },
+                CatalogInclusionRule { conditions: vec![slacks.clone().into()], inclusions: vec![jeans.clone().into()] },
             ],
+            ..CatalogAssembly::default()
         })
             .expect_err("expected build to return Error");
 
@@ -669,3 +884,397 @@ mod inclusion_rules_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod attribute_rules_tests {
+    use catalog::fixtures::sorted_outfits;
+    use catalog_builder::validation::CatalogBuilderError;
+    use types::{Family, Item};
+
+    use super::build_catalog;
+    use super::CatalogAssembly;
+    use super::CatalogExclusionRule;
+    use super::CatalogInclusionRule;
+    use super::RuleTarget;
+
+    fn attribute(attribute: &str, value: &str) -> RuleTarget {
+        RuleTarget::Attribute { attribute: attribute.into(), value: value.into() }
+    }
+
+    #[test]
+    fn exclusion_rule_targets_items_by_attribute() {
+        let blue = Item::from("shirts:blue");
+        let red = Item::from("shirts:red");
+        let orange = Item::from("shirts:orange");
+
+        let jeans = Item::from("pants:jeans");
+        let slacks = Item::from("pants:slacks");
+
+        let shirts = Family::from("shirts");
+        let pants = Family::from("pants");
+
+        let catalog = build_catalog(CatalogAssembly {
+            families: btreemap! {
+                shirts => vec![red.clone(), blue.clone(), orange.clone()],
+                pants => vec![jeans.clone(), slacks.clone()],
+            },
+            exclusions: vec![
+                CatalogExclusionRule { conditions: vec![attribute("color", "warm")], exclusions: vec![attribute("fabric", "denim")] },
+            ],
+            inclusions: vec![],
+            attributes: btreemap! {
+                red.clone() => btreemap! { "color".into() => "warm".into() },
+                orange.clone() => btreemap! { "color".into() => "warm".into() },
+                blue.clone() => btreemap! { "color".into() => "cool".into() },
+                jeans.clone() => btreemap! { "fabric".into() => "denim".into() },
+            },
+            ..CatalogAssembly::default()
+        })
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![jeans.clone(), blue.clone()],
+            vec![slacks.clone(), blue.clone()],
+            vec![slacks.clone(), orange.clone()],
+            vec![slacks.clone(), red.clone()],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn inclusion_rule_is_satisfied_by_any_item_with_attribute() {
+        let blue = Item::from("shirts:blue");
+        let red = Item::from("shirts:red");
+
+        let jeans = Item::from("pants:jeans");
+        let slacks = Item::from("pants:slacks");
+        let shorts = Item::from("pants:shorts");
+
+        let shirts = Family::from("shirts");
+        let pants = Family::from("pants");
+
+        let catalog = build_catalog(CatalogAssembly {
+            families: btreemap! {
+                shirts => vec![red.clone(), blue.clone()],
+                pants => vec![jeans.clone(), slacks.clone(), shorts.clone()],
+            },
+            exclusions: vec![],
+            inclusions: vec![
+                CatalogInclusionRule { conditions: vec![red.clone().into()], inclusions: vec![attribute("length", "long")] },
+            ],
+            attributes: btreemap! {
+                jeans.clone() => btreemap! { "length".into() => "long".into() },
+                slacks.clone() => btreemap! { "length".into() => "long".into() },
+                shorts.clone() => btreemap! { "length".into() => "short".into() },
+            },
+            ..CatalogAssembly::default()
+        })
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![jeans.clone(), blue.clone()],
+            vec![jeans.clone(), red.clone()],
+            vec![shorts.clone(), blue.clone()],
+            vec![slacks.clone(), blue.clone()],
+            vec![slacks.clone(), red.clone()],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn attribute_targets_must_match_an_item() {
+        let blue = Item::from("shirts:blue");
+        let red = Item::from("shirts:red");
+
+        let jeans = Item::from("pants:jeans");
+        let slacks = Item::from("pants:slacks");
+
+        let shirts = Family::from("shirts");
+        let pants = Family::from("pants");
+
+        let error = build_catalog(CatalogAssembly {
+            families: btreemap! {
+                shirts => vec![red.clone(), blue.clone()],
+                pants => vec![jeans.clone(), slacks.clone()],
+            },
+            exclusions: vec![
+                CatalogExclusionRule { conditions: vec![red.clone().into()], exclusions: vec![attribute("fabric", "silk")] },
+            ],
+            inclusions: vec![],
+            attributes: btreemap! {
+                jeans.clone() => btreemap! { "fabric".into() => "denim".into() },
+            },
+            ..CatalogAssembly::default()
+        })
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::UnmatchedAttribute { attribute: "fabric".into(), value: "silk".into() },
+            error
+        );
+    }
+
+    #[test]
+    fn attributes_must_belong_to_an_item_with_a_family() {
+        let blue = Item::from("shirts:blue");
+        let red = Item::from("shirts:red");
+        let black = Item::from("shirts:black");
+
+        let shirts = Family::from("shirts");
+
+        let error = build_catalog(CatalogAssembly {
+            families: btreemap! {
+                shirts => vec![red.clone(), blue.clone()],
+            },
+            exclusions: vec![],
+            inclusions: vec![],
+            attributes: btreemap! {
+                black.clone() => btreemap! { "color".into() => "dark".into() },
+            },
+            ..CatalogAssembly::default()
+        })
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AttributeMissingFamily { item: black },
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod attribute_relation_rules_tests {
+    use catalog::fixtures::sorted_outfits;
+    use catalog_builder::validation::CatalogBuilderError;
+    use types::{Family, Item};
+
+    use super::AttributeRelation;
+    use super::build_catalog;
+    use super::build_catalog_with_report;
+    use super::CatalogAssembly;
+    use super::CatalogAttributeRule;
+
+    fn assembly(relation: AttributeRelation, attribute: &str, families: (&str, &str)) -> CatalogAssembly {
+        let blue_shirt = Item::from("shirts:blue");
+        let red_shirt = Item::from("shirts:red");
+
+        let blue_tie = Item::from("ties:blue");
+        let red_tie = Item::from("ties:red");
+        let striped_tie = Item::from("ties:striped");
+
+        let shirts = Family::from("shirts");
+        let ties = Family::from("ties");
+
+        CatalogAssembly {
+            families: btreemap! {
+                shirts => vec![red_shirt.clone(), blue_shirt.clone()],
+                ties => vec![red_tie.clone(), blue_tie.clone(), striped_tie.clone()],
+            },
+            exclusions: vec![],
+            inclusions: vec![],
+            attributes: btreemap! {
+                red_shirt => btreemap! { "color".into() => "red".into() },
+                blue_shirt => btreemap! { "color".into() => "blue".into() },
+                red_tie => btreemap! { "color".into() => "red".into() },
+                blue_tie => btreemap! { "color".into() => "blue".into() },
+            },
+            attribute_rules: vec![
+                CatalogAttributeRule {
+                    attribute: attribute.into(),
+                    families: (families.0.into(), families.1.into()),
+                    relation,
+                },
+            ],
+            ..CatalogAssembly::default()
+        }
+    }
+
+    #[test]
+    fn equal_relation_keeps_outfits_with_matching_attributes() {
+        let catalog = build_catalog(assembly(AttributeRelation::Equal, "color", ("shirts", "ties")))
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![Item::from("shirts:blue"), Item::from("ties:blue")],
+            vec![Item::from("shirts:red"), Item::from("ties:red")],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn not_equal_relation_removes_outfits_with_matching_attributes() {
+        let catalog = build_catalog(assembly(AttributeRelation::NotEqual, "color", ("shirts", "ties")))
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![Item::from("shirts:blue"), Item::from("ties:red")],
+            vec![Item::from("shirts:blue"), Item::from("ties:striped")],
+            vec![Item::from("shirts:red"), Item::from("ties:blue")],
+            vec![Item::from("shirts:red"), Item::from("ties:striped")],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn attribute_rules_remove_the_same_outfits_when_reporting() {
+        for &relation in &[AttributeRelation::Equal, AttributeRelation::NotEqual] {
+            for &families in &[("shirts", "ties"), ("ties", "shirts")] {
+                let catalog = build_catalog(assembly(relation, "color", families))
+                    .expect("expected build to return Catalog");
+                let (reported, _) = build_catalog_with_report(assembly(relation, "color", families))
+                    .expect("expected build to return Catalog");
+
+                assert_eq!(sorted_outfits(reported.combinations()), sorted_outfits(catalog.combinations()), "{:?} {:?}", relation, families);
+            }
+        }
+    }
+
+    #[test]
+    fn attribute_rule_families_must_exist() {
+        let error = build_catalog(assembly(AttributeRelation::Equal, "color", ("shirts", "belts")))
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AttributeRuleMissingFamily { family: "belts".into() },
+            error
+        );
+    }
+
+    #[test]
+    fn attribute_rule_families_must_be_different() {
+        let error = build_catalog(assembly(AttributeRelation::Equal, "color", ("ties", "ties")))
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AttributeRuleFamilyConflict { family: "ties".into() },
+            error
+        );
+    }
+
+    #[test]
+    fn attribute_rule_attribute_must_exist() {
+        let error = build_catalog(assembly(AttributeRelation::Equal, "pattern", ("shirts", "ties")))
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AttributeRuleUnknownAttribute { attribute: "pattern".into() },
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod aggregate_rules_tests {
+    use catalog::fixtures::sorted_outfits;
+    use catalog::{Aggregate, AggregateConstraint, Bound, Comparison};
+    use catalog_builder::validation::CatalogBuilderError;
+    use types::{Family, Item};
+
+    use super::build_catalog;
+    use super::CatalogAssembly;
+
+    fn assembly(aggregate_rules: Vec<AggregateConstraint>) -> CatalogAssembly {
+        let fast = Item::from("cpu:fast");
+        let slow = Item::from("cpu:slow");
+
+        let small = Item::from("psu:small");
+        let large = Item::from("psu:large");
+
+        let cpus = Family::from("cpu");
+        let psus = Family::from("psu");
+
+        CatalogAssembly {
+            families: btreemap! {
+                cpus => vec![fast.clone(), slow.clone()],
+                psus => vec![small.clone(), large.clone()],
+            },
+            numeric_attributes: btreemap! {
+                fast => btreemap! { "watts".into() => 150.0, "price".into() => 400.0 },
+                slow => btreemap! { "watts".into() => 65.0, "price".into() => 150.0 },
+                small => btreemap! { "capacity".into() => 100.0, "price".into() => 50.0 },
+                large => btreemap! { "capacity".into() => 200.0, "price".into() => 120.0 },
+            },
+            aggregate_rules,
+            ..CatalogAssembly::default()
+        }
+    }
+
+    #[test]
+    fn aggregate_rule_compares_sum_to_value() {
+        let budget = AggregateConstraint::new("price", Aggregate::Sum, Comparison::AtMost, Bound::Value(300.0), vec![]);
+
+        let catalog = build_catalog(assembly(vec![budget]))
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![Item::from("cpu:slow"), Item::from("psu:large")],
+            vec![Item::from("cpu:slow"), Item::from("psu:small")],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn aggregate_rule_compares_sum_to_attribute_of_another_family() {
+        let capacity = AggregateConstraint::new(
+            "watts",
+            Aggregate::Sum,
+            Comparison::AtMost,
+            Bound::Attribute { family: "psu".into(), attribute: "capacity".into() },
+            vec!["cpu".into()],
+        );
+
+        let catalog = build_catalog(assembly(vec![capacity]))
+            .expect("expected build to return Catalog");
+
+        let expected = vec![
+            vec![Item::from("cpu:fast"), Item::from("psu:large")],
+            vec![Item::from("cpu:slow"), Item::from("psu:large")],
+            vec![Item::from("cpu:slow"), Item::from("psu:small")],
+        ];
+        assert_eq!(
+            sorted_outfits(expected),
+            sorted_outfits(catalog.combinations())
+        );
+    }
+
+    #[test]
+    fn aggregate_rule_attribute_must_exist() {
+        let weight = AggregateConstraint::new("weight", Aggregate::Max, Comparison::AtMost, Bound::Value(10.0), vec![]);
+
+        let error = build_catalog(assembly(vec![weight]))
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AggregateRuleUnknownAttribute { attribute: "weight".into() },
+            error
+        );
+    }
+
+    #[test]
+    fn aggregate_rule_families_must_exist() {
+        let gpu = AggregateConstraint::new("watts", Aggregate::Sum, Comparison::AtMost, Bound::Value(10.0), vec!["gpu".into()]);
+
+        let error = build_catalog(assembly(vec![gpu]))
+            .expect_err("expected build to return Error");
+
+        assert_eq!(
+            CatalogBuilderError::AggregateRuleMissingFamily { family: "gpu".into() },
+            error
+        );
+    }
+}
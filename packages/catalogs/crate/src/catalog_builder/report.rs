@@ -0,0 +1,213 @@
+use std::collections::BTreeSet;
+
+/// An exclusion or inclusion rule, by its index in the assembly.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(tag = "type")]
+pub enum RuleId {
+    Exclusion { index: usize },
+    Inclusion { index: usize },
+}
+
+/// What a single rule removes. `unique_outfits` are the outfits no other rule removes,
+/// i.e. the outfits the catalog would gain if the rule were dropped.
+/// `overlapping` are the rules that remove at least one of the same outfits.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RuleEffect {
+    rule: RuleId,
+    removed_outfits: usize,
+    unique_outfits: usize,
+    overlapping: Vec<RuleId>,
+}
+
+impl RuleEffect {
+    pub fn rule(&self) -> RuleId {
+        self.rule
+    }
+
+    pub fn removed_outfits(&self) -> usize {
+        self.removed_outfits
+    }
+
+    pub fn unique_outfits(&self) -> usize {
+        self.unique_outfits
+    }
+
+    pub fn overlapping(&self) -> &[RuleId] {
+        &self.overlapping
+    }
+}
+
+/// The effect of every exclusion and inclusion rule, in assembly order.
+/// Attribute and aggregate rules are not reported on, but outfits they remove don't count as unique to another rule.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RuleReport {
+    outfits: usize,
+    rules: Vec<RuleEffect>,
+}
+
+impl RuleReport {
+    /// The number of outfits before any rule was applied.
+    pub fn outfits(&self) -> usize {
+        self.outfits
+    }
+
+    pub fn rules(&self) -> &[RuleEffect] {
+        &self.rules
+    }
+
+    pub fn rule(&self, rule: RuleId) -> Option<&RuleEffect> {
+        self.rules.iter().find(|effect| effect.rule == rule)
+    }
+
+    /// Rules that remove no outfits at all.
+    pub fn unused(&self) -> Vec<RuleId> {
+        self.rules.iter()
+            .filter(|effect| effect.removed_outfits == 0)
+            .map(|effect| effect.rule)
+            .collect()
+    }
+
+    /// Rules that only remove outfits some other rule removes as well.
+    pub fn redundant(&self) -> Vec<RuleId> {
+        self.rules.iter()
+            .filter(|effect| effect.removed_outfits > 0 && effect.unique_outfits == 0)
+            .map(|effect| effect.rule)
+            .collect()
+    }
+}
+
+/// Counts up rule effects while the catalog is built.
+/// Rules are numbered exclusions first, then inclusions.
+pub(super) struct RuleTally {
+    exclusions: usize,
+    outfits: usize,
+    removed: Vec<usize>,
+    unique: Vec<usize>,
+    overlapping: Vec<BTreeSet<usize>>,
+}
+
+impl RuleTally {
+    pub(super) fn new(exclusions: usize, inclusions: usize) -> Self {
+        let rules = exclusions + inclusions;
+
+        RuleTally {
+            exclusions,
+            outfits: 0,
+            removed: vec![0; rules],
+            unique: vec![0; rules],
+            overlapping: vec![BTreeSet::new(); rules],
+        }
+    }
+
+    /// Records one outfit, given whether each rule rejects it and whether any other rule does.
+    /// Returns whether the outfit stays in the catalog.
+    pub(super) fn record<I: IntoIterator<Item=bool>>(&mut self, rejections: I, rejected_by_others: bool) -> bool {
+        let rejected = rejections.into_iter()
+            .enumerate()
+            .filter(|&(_, rejected)| rejected)
+            .map(|(rule, _)| rule)
+            .collect::<Vec<_>>();
+
+        self.outfits += 1;
+        for &rule in &rejected {
+            self.removed[rule] += 1;
+            self.overlapping[rule].extend(rejected.iter().filter(|&&other| other != rule));
+        }
+        if let (&[rule], false) = (rejected.as_slice(), rejected_by_others) {
+            self.unique[rule] += 1;
+        }
+
+        rejected.is_empty() && !rejected_by_others
+    }
+
+    pub(super) fn into_report(self) -> RuleReport {
+        let exclusions = self.exclusions;
+        let id = |rule: usize| if rule < exclusions {
+            RuleId::Exclusion { index: rule }
+        } else {
+            RuleId::Inclusion { index: rule - exclusions }
+        };
+
+        let rules = self.removed.into_iter()
+            .zip(self.unique)
+            .zip(self.overlapping)
+            .enumerate()
+            .map(|(rule, ((removed_outfits, unique_outfits), overlapping))| RuleEffect {
+                rule: id(rule),
+                removed_outfits,
+                unique_outfits,
+                overlapping: overlapping.into_iter().map(id).collect(),
+            })
+            .collect();
+
+        RuleReport { outfits: self.outfits, rules }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{build_catalog_with_report, CatalogAssembly, CatalogExclusionRule, CatalogInclusionRule, RuleTarget};
+    use super::RuleId;
+
+    fn targets(items: &[&str]) -> Vec<RuleTarget> {
+        items.iter().map(|&item| RuleTarget::Item(item.into())).collect()
+    }
+
+    fn assembly(exclusions: Vec<CatalogExclusionRule>, inclusions: Vec<CatalogInclusionRule>) -> CatalogAssembly {
+        CatalogAssembly::new(
+            btreemap! {
+                "shirts".into() => vec!["shirts:red".into(), "shirts:blue".into()],
+                "pants".into() => vec!["pants:jeans".into(), "pants:slacks".into()],
+            },
+            exclusions,
+            inclusions,
+        )
+    }
+
+    #[test]
+    fn rules_report_total_and_unique_removals() {
+        let assembly = assembly(
+            vec![
+                CatalogExclusionRule::new(targets(&["shirts:red"]), targets(&["pants:jeans", "pants:slacks"])),
+                CatalogExclusionRule::new(targets(&["shirts:red"]), targets(&["pants:jeans"])),
+            ],
+            vec![CatalogInclusionRule::new(targets(&["shirts:blue"]), targets(&["pants:slacks"]))],
+        );
+
+        let (catalog, report) = build_catalog_with_report(assembly).unwrap();
+
+        assert_eq!(1, catalog.outfit_count());
+        assert_eq!(4, report.outfits());
+
+        let broad = report.rule(RuleId::Exclusion { index: 0 }).unwrap();
+        assert_eq!(2, broad.removed_outfits());
+        assert_eq!(1, broad.unique_outfits());
+        assert_eq!(&[RuleId::Exclusion { index: 1 }], broad.overlapping());
+
+        let narrow = report.rule(RuleId::Exclusion { index: 1 }).unwrap();
+        assert_eq!(1, narrow.removed_outfits());
+        assert_eq!(0, narrow.unique_outfits());
+
+        let inclusion = report.rule(RuleId::Inclusion { index: 0 }).unwrap();
+        assert_eq!(1, inclusion.removed_outfits());
+        assert_eq!(1, inclusion.unique_outfits());
+        assert!(inclusion.overlapping().is_empty());
+
+        assert_eq!(vec![RuleId::Exclusion { index: 1 }], report.redundant());
+        assert!(report.unused().is_empty());
+    }
+
+    #[test]
+    fn rules_that_match_no_outfit_are_unused() {
+        let assembly = assembly(
+            vec![CatalogExclusionRule::new(targets(&["shirts:red", "shirts:blue"]), targets(&["pants:jeans"]))],
+            vec![],
+        );
+
+        let (catalog, report) = build_catalog_with_report(assembly).unwrap();
+
+        assert_eq!(4, catalog.outfit_count());
+        assert_eq!(vec![RuleId::Exclusion { index: 0 }], report.unused());
+        assert!(report.redundant().is_empty());
+    }
+}
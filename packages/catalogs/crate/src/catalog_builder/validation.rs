@@ -1,26 +1,109 @@
 use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
 
 use itertools::Itertools;
 
-use types::{Family, Item};
+use catalog::AggregateConstraint;
+use errors::{CatalogsError, StatusClass};
+use types::{Attributes, Family, Item, NumericAttributes};
 
-use super::{CatalogExclusionRule, CatalogInclusionRule};
+use super::{CatalogAttributeRule, CatalogExclusionRule, CatalogInclusionRule, RuleTarget};
 
-use self::CatalogBuilderError::{CompoundError, ExclusionFamilyConflict, ExclusionMissingFamily, InclusionFamilyConflict, InclusionMissingFamily, MultipleFamiliesRegistered};
+use self::CatalogBuilderError::{AggregateRuleMissingFamily, AggregateRuleUnknownAttribute, AttributeMissingFamily, AttributeRuleFamilyConflict, AttributeRuleMissingFamily, AttributeRuleUnknownAttribute, BadArgument, CompoundError, EmptyCatalog, ExclusionFamilyConflict, ExclusionMissingFamily, InclusionFamilyConflict, InclusionMissingFamily, MultipleFamiliesRegistered, NumericAttributeMissingFamily, UnknownExclusionRule, UnknownInclusionRule, UnknownItem, UnmatchedAttribute};
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum CatalogBuilderError {
     EmptyCatalog,
+    BadArgument { argument: String },
     InclusionMissingFamily { item: String },
     ExclusionMissingFamily { item: String },
     MultipleFamiliesRegistered { item: String, families: Vec<String> },
     InclusionFamilyConflict { family: String, items: Vec<String> },
     ExclusionFamilyConflict { family: String, items: Vec<String> },
+    AttributeMissingFamily { item: String },
+    UnmatchedAttribute { attribute: String, value: String },
+    AttributeRuleMissingFamily { family: String },
+    AttributeRuleFamilyConflict { family: String },
+    AttributeRuleUnknownAttribute { attribute: String },
+    NumericAttributeMissingFamily { item: String },
+    AggregateRuleMissingFamily { family: String },
+    AggregateRuleUnknownAttribute { attribute: String },
     CompoundError { errors: Vec<CatalogBuilderError> },
+    UnknownExclusionRule { index: usize },
+    UnknownInclusionRule { index: usize },
+    UnknownItem { item: String },
+}
+
+impl fmt::Display for CatalogBuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmptyCatalog => write!(f, "families are required to build a catalog"),
+            BadArgument { argument } => write!(f, "{} could not be read", argument),
+            InclusionMissingFamily { item } => write!(f, "inclusion rule item '{}' is not registered to a family", item),
+            ExclusionMissingFamily { item } => write!(f, "exclusion rule item '{}' is not registered to a family", item),
+            MultipleFamiliesRegistered { item, families } =>
+                write!(f, "item '{}' is registered to multiple families: {}", item, families.join(", ")),
+            InclusionFamilyConflict { family, items } =>
+                write!(f, "inclusion rule includes items from its own family '{}': {}", family, items.join(", ")),
+            ExclusionFamilyConflict { family, items } =>
+                write!(f, "exclusion rule excludes items from its own family '{}': {}", family, items.join(", ")),
+            AttributeMissingFamily { item } => write!(f, "attributes are given for item '{}' which is not registered to a family", item),
+            UnmatchedAttribute { attribute, value } => write!(f, "no item has attribute {}={}", attribute, value),
+            AttributeRuleMissingFamily { family } => write!(f, "attribute rule refers to unknown family '{}'", family),
+            AttributeRuleFamilyConflict { family } => write!(f, "attribute rule relates family '{}' to itself", family),
+            AttributeRuleUnknownAttribute { attribute } => write!(f, "attribute rule refers to unknown attribute '{}'", attribute),
+            NumericAttributeMissingFamily { item } =>
+                write!(f, "numeric attributes are given for item '{}' which is not registered to a family", item),
+            AggregateRuleMissingFamily { family } => write!(f, "aggregate rule refers to unknown family '{}'", family),
+            AggregateRuleUnknownAttribute { attribute } => write!(f, "aggregate rule refers to unknown attribute '{}'", attribute),
+            CompoundError { errors } => write!(f, "{}", errors.iter().map(|err| err.to_string()).join("; ")),
+            UnknownExclusionRule { index } => write!(f, "there is no exclusion rule {}", index),
+            UnknownInclusionRule { index } => write!(f, "there is no inclusion rule {}", index),
+            UnknownItem { item } => write!(f, "item '{}' is not registered to a family", item),
+        }
+    }
+}
+
+impl Error for CatalogBuilderError {}
+
+impl CatalogsError for CatalogBuilderError {
+    fn code(&self) -> &'static str {
+        match self {
+            EmptyCatalog => "EMPTY_CATALOG",
+            BadArgument { .. } => "BAD_ARGUMENT",
+            InclusionMissingFamily { .. } => "INCLUSION_MISSING_FAMILY",
+            ExclusionMissingFamily { .. } => "EXCLUSION_MISSING_FAMILY",
+            MultipleFamiliesRegistered { .. } => "MULTIPLE_FAMILIES_REGISTERED",
+            InclusionFamilyConflict { .. } => "INCLUSION_FAMILY_CONFLICT",
+            ExclusionFamilyConflict { .. } => "EXCLUSION_FAMILY_CONFLICT",
+            AttributeMissingFamily { .. } => "ATTRIBUTE_MISSING_FAMILY",
+            UnmatchedAttribute { .. } => "UNMATCHED_ATTRIBUTE",
+            AttributeRuleMissingFamily { .. } => "ATTRIBUTE_RULE_MISSING_FAMILY",
+            AttributeRuleFamilyConflict { .. } => "ATTRIBUTE_RULE_FAMILY_CONFLICT",
+            AttributeRuleUnknownAttribute { .. } => "ATTRIBUTE_RULE_UNKNOWN_ATTRIBUTE",
+            NumericAttributeMissingFamily { .. } => "NUMERIC_ATTRIBUTE_MISSING_FAMILY",
+            AggregateRuleMissingFamily { .. } => "AGGREGATE_RULE_MISSING_FAMILY",
+            AggregateRuleUnknownAttribute { .. } => "AGGREGATE_RULE_UNKNOWN_ATTRIBUTE",
+            CompoundError { .. } => "COMPOUND_ERROR",
+            UnknownExclusionRule { .. } => "UNKNOWN_EXCLUSION_RULE",
+            UnknownInclusionRule { .. } => "UNKNOWN_INCLUSION_RULE",
+            UnknownItem { .. } => "UNKNOWN_ITEM",
+        }
+    }
+
+    /// Every builder error describes a problem with the assembly or edit that was handed in.
+    fn status(&self) -> StatusClass {
+        StatusClass::InvalidArgument
+    }
 }
 
 impl CatalogBuilderError {
+    pub(crate) fn bad_argument(argument: &str) -> Self {
+        BadArgument { argument: argument.into() }
+    }
+
     fn exclusion_family_conflict(family: Family, items: Vec<Item>) -> Self {
         ExclusionFamilyConflict { family, items }
     }
@@ -41,14 +124,23 @@ impl CatalogBuilderError {
 pub fn validate_catalog(
     families: &BTreeMap<Family, Vec<Item>>,
     item_index: &HashMap<Item, Family>,
+    attributes: &BTreeMap<Item, Attributes>,
     exclusions: &[CatalogExclusionRule],
     inclusions: &[CatalogInclusionRule],
+    attribute_rules: &[CatalogAttributeRule],
+    numeric_attributes: &BTreeMap<Item, NumericAttributes>,
+    aggregate_rules: &[AggregateConstraint],
 ) -> Result<(), CatalogBuilderError> {
     let conflicts =
         vec![
             find_conflicting_families(families, item_index),
-            find_illegal_exclusion_rules(exclusions, item_index),
-            find_illegal_inclusion_rules(inclusions, item_index)
+            find_attributes_without_family(attributes, item_index),
+            find_unmatched_attributes(exclusions, inclusions, attributes),
+            find_illegal_exclusion_rules(exclusions, item_index, attributes),
+            find_illegal_inclusion_rules(inclusions, item_index, attributes),
+            find_illegal_attribute_rules(attribute_rules, families, attributes),
+            find_numeric_attributes_without_family(numeric_attributes, item_index),
+            find_illegal_aggregate_rules(aggregate_rules, families, numeric_attributes)
         ]
             .iter()
             .flatten()
@@ -87,13 +179,53 @@ fn find_conflicting_families(
         .collect::<Vec<CatalogBuilderError>>()
 }
 
+fn find_attributes_without_family(
+    attributes: &BTreeMap<Item, Attributes>,
+    item_index: &HashMap<Item, Family>,
+) -> Vec<CatalogBuilderError> {
+    attributes.keys()
+        .filter(|item| !item_index.contains_key(*item))
+        .map(|item| AttributeMissingFamily { item: item.clone() })
+        .collect()
+}
+
+fn find_numeric_attributes_without_family(
+    numeric_attributes: &BTreeMap<Item, NumericAttributes>,
+    item_index: &HashMap<Item, Family>,
+) -> Vec<CatalogBuilderError> {
+    numeric_attributes.keys()
+        .filter(|item| !item_index.contains_key(*item))
+        .map(|item| NumericAttributeMissingFamily { item: item.clone() })
+        .collect()
+}
+
+fn find_unmatched_attributes(
+    exclusions: &[CatalogExclusionRule],
+    inclusions: &[CatalogInclusionRule],
+    attributes: &BTreeMap<Item, Attributes>,
+) -> Vec<CatalogBuilderError> {
+    let exclusion_targets = exclusions.iter().flat_map(|rule| rule.targets());
+    let inclusion_targets = inclusions.iter().flat_map(|rule| rule.targets());
+
+    exclusion_targets.chain(inclusion_targets)
+        .filter_map(|target| match target {
+            RuleTarget::Attribute { attribute, value } if target.expand(attributes).is_empty() =>
+                Some(UnmatchedAttribute { attribute: attribute.clone(), value: value.clone() }),
+            _ => None,
+        })
+        .collect()
+}
+
 fn find_illegal_exclusion_rules(
     rules: &[CatalogExclusionRule],
     item_index: &HashMap<Item, Family>,
+    attributes: &BTreeMap<Item, Attributes>,
 ) -> Vec<CatalogBuilderError> {
     let rules = rules.iter()
-        .flat_map(|rule| rule.conditions.iter()
-            .map(|selection| (selection, &rule.exclusions))
+        .map(|rule| rule.expand(attributes))
+        .flat_map(|rule| rule.conditions().iter()
+            .flatten()
+            .map(|selection| (selection.clone(), rule.exclusions().to_vec()))
             .collect::<Vec<_>>());
 
     find_family_conflicts_rules(
@@ -107,11 +239,21 @@ fn find_illegal_exclusion_rules(
 fn find_illegal_inclusion_rules(
     rules: &[CatalogInclusionRule],
     item_index: &HashMap<Item, Family>,
+    attributes: &BTreeMap<Item, Attributes>,
 ) -> Vec<CatalogBuilderError> {
     let rules = rules.iter()
-        .flat_map(|rule| rule.conditions.iter()
-            .map(|selection| (selection, &rule.inclusions))
-            .collect::<Vec<_>>());
+        .map(|rule| rule.expand(attributes))
+        .flat_map(|rule| {
+            let inclusions = rule.inclusions().iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+
+            rule.conditions().iter()
+                .flatten()
+                .map(|selection| (selection.clone(), inclusions.clone()))
+                .collect::<Vec<_>>()
+        });
 
     find_family_conflicts_rules(
         rules,
@@ -121,14 +263,69 @@ fn find_illegal_inclusion_rules(
     )
 }
 
-fn find_family_conflicts_rules<'a, B: IntoIterator<Item=(&'a Item, &'a Vec<Item>)>>(
+fn find_illegal_attribute_rules(
+    rules: &[CatalogAttributeRule],
+    families: &BTreeMap<Family, Vec<Item>>,
+    attributes: &BTreeMap<Item, Attributes>,
+) -> Vec<CatalogBuilderError> {
+    rules.iter()
+        .flat_map(|rule| {
+            let (left, right) = &rule.families;
+
+            let missing_families = vec![left, right].into_iter()
+                .filter(|family| !families.contains_key(*family))
+                .map(|family| AttributeRuleMissingFamily { family: family.clone() })
+                .collect::<Vec<_>>();
+            if !missing_families.is_empty() {
+                return missing_families;
+            }
+
+            if left == right {
+                return vec![AttributeRuleFamilyConflict { family: left.clone() }];
+            }
+
+            let attribute_is_known = families[left].iter()
+                .chain(families[right].iter())
+                .filter_map(|item| attributes.get(item))
+                .any(|item_attributes| item_attributes.contains_key(&rule.attribute));
+
+            if attribute_is_known {
+                vec![]
+            } else {
+                vec![AttributeRuleUnknownAttribute { attribute: rule.attribute.clone() }]
+            }
+        })
+        .collect()
+}
+
+fn find_illegal_aggregate_rules(
+    rules: &[AggregateConstraint],
+    families: &BTreeMap<Family, Vec<Item>>,
+    numeric_attributes: &BTreeMap<Item, NumericAttributes>,
+) -> Vec<CatalogBuilderError> {
+    let missing_families = rules.iter()
+        .flat_map(|rule| rule.families())
+        .filter(|family| !families.contains_key(*family))
+        .map(|family| AggregateRuleMissingFamily { family: family.clone() });
+
+    let unknown_attributes = rules.iter()
+        .flat_map(|rule| rule.attributes())
+        .filter(|attribute| !numeric_attributes.values().any(|values| values.contains_key(*attribute)))
+        .map(|attribute| AggregateRuleUnknownAttribute { attribute: attribute.into() });
+
+    missing_families
+        .chain(unknown_attributes)
+        .collect()
+}
+
+fn find_family_conflicts_rules<B: IntoIterator<Item=(Item, Vec<Item>)>>(
     rules: B,
     item_index: &HashMap<Item, Family>,
     conflict_error: fn(Family, Vec<Item>) -> CatalogBuilderError,
     missing_family_error: fn(Item) -> CatalogBuilderError,
 ) -> Vec<CatalogBuilderError> {
-    let find_selections_and_items_without_families = |(selection, items): (&Item, &Vec<Item>)| {
-        let selection_family = match item_index.get(selection) {
+    let find_selections_and_items_without_families = |(selection, items): (Item, Vec<Item>)| {
+        let selection_family = match item_index.get(&selection) {
             None => return vec![missing_family_error(selection.clone())],
             Some(selection_family) => selection_family,
         };
@@ -155,4 +352,4 @@ fn find_family_conflicts_rules<'a, B: IntoIterator<Item=(&'a Item, &'a Vec<Item>
     rules.into_iter()
         .flat_map(find_selections_and_items_without_families)
         .collect::<Vec<_>>()
-}
\ No newline at end of file
+}
@@ -0,0 +1,55 @@
+use std::error::Error;
+
+/// The canonical status an error should be reported with, whichever binding reports it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum StatusClass {
+    InvalidArgument,
+    FailedPrecondition,
+    NotFound,
+    AlreadyExists,
+    Internal,
+}
+
+impl StatusClass {
+    /// The canonical gRPC status code, as listed in `google.rpc.Code`.
+    pub fn grpc_code(&self) -> i32 {
+        match self {
+            StatusClass::InvalidArgument => 3,
+            StatusClass::NotFound => 5,
+            StatusClass::AlreadyExists => 6,
+            StatusClass::FailedPrecondition => 9,
+            StatusClass::Internal => 13,
+        }
+    }
+
+    /// The HTTP status the gRPC code maps to.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            StatusClass::InvalidArgument => 400,
+            StatusClass::NotFound => 404,
+            StatusClass::AlreadyExists => 409,
+            StatusClass::FailedPrecondition => 400,
+            StatusClass::Internal => 500,
+        }
+    }
+}
+
+/// Errors with a stable, machine readable code. Codes never change once released,
+/// so clients may match on them instead of on messages.
+pub trait CatalogsError: Error {
+    fn code(&self) -> &'static str;
+
+    fn status(&self) -> StatusClass;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusClass;
+
+    #[test]
+    fn status_classes_map_to_canonical_codes() {
+        assert_eq!((3, 400), (StatusClass::InvalidArgument.grpc_code(), StatusClass::InvalidArgument.http_status()));
+        assert_eq!((9, 400), (StatusClass::FailedPrecondition.grpc_code(), StatusClass::FailedPrecondition.http_status()));
+        assert_eq!((13, 500), (StatusClass::Internal.grpc_code(), StatusClass::Internal.http_status()));
+    }
+}
@@ -9,20 +9,27 @@ extern crate maplit;
 extern crate reduce;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "storage")]
+extern crate serde_json;
 extern crate wasm_bindgen;
 extern crate weave;
 extern crate serde;
 
+use serde::de::DeserializeOwned;
 use wasm_bindgen::prelude::*;
 
-use catalog::CatalogState;
-use catalog_builder::CatalogAssembly;
+use catalog::{CatalogError, CatalogState, CompletionMode, Optimization, OptionsQuery};
+use catalog_builder::{AssemblyEdit, CatalogAssembly, CatalogBuilderError};
 use types::Item;
 
-mod catalog;
-mod catalog_builder;
+pub mod catalog;
+pub mod catalog_builder;
+pub mod errors;
+mod session;
+#[cfg(feature = "storage")]
+pub mod storage;
 mod utils;
-mod types;
+pub mod types;
 
 #[wasm_bindgen(js_name = findOutfitsWasm)]
 pub fn find_outfits(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue) -> js_sys::Promise {
@@ -45,6 +52,120 @@ pub fn find_options(catalog_state: &JsValue, selections: &JsValue, exclusions: &
         .into_promise()
 }
 
+#[wasm_bindgen(js_name = findFamilyOptionsWasm)]
+pub fn find_family_options(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.family_options(&selections, &exclusions))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = findCountedOptionsWasm)]
+pub fn find_counted_options(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.counted_options(&selections, &exclusions))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = findPricedOptionsWasm)]
+pub fn find_priced_options(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue, attribute: &str) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.priced_options(&selections, &exclusions, attribute))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = findConflictOptionsWasm)]
+pub fn find_conflict_options(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.conflict_options(&selections, &exclusions))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = diffCatalogsWasm)]
+pub fn diff_catalogs(catalog_state: &JsValue, after_state: &JsValue) -> js_sys::Promise {
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| CatalogState::from_jsvalue(after_state).and_then(|after| state.diff(&after)))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = rebaseStateWasm)]
+pub fn rebase_state(catalog_state: &JsValue, target_state: &JsValue) -> js_sys::Promise {
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| CatalogState::from_jsvalue(target_state).and_then(|target| state.rebase_onto(&target)))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = findOptionsBatchWasm)]
+pub fn find_options_batch(catalog_state: &JsValue, queries: &JsValue) -> js_sys::Promise {
+    let queries: Result<Vec<OptionsQuery>, _> = from_argument(queries, "queries", CatalogError::bad_argument);
+
+    queries
+        .and_then(|queries| CatalogState::from_jsvalue(catalog_state).and_then(|state| state.batch_options(&queries)))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = recommendFamiliesWasm)]
+pub fn recommend_families(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.recommend_families(&selections, &exclusions))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = completeOutfitWasm)]
+pub fn complete_outfit(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue, mode: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+    let mode: Result<CompletionMode, _> = from_argument(mode, "mode", CatalogError::bad_argument);
+
+    mode
+        .and_then(|mode| CatalogState::from_jsvalue(catalog_state).and_then(|state| state.complete(&selections, &exclusions, mode)))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = optimizeOutfitsWasm)]
+pub fn optimize_outfits(catalog_state: &JsValue, selections: &JsValue, exclusions: &JsValue, optimization: &JsValue) -> js_sys::Promise {
+    let selections: Vec<Item> = to_items(selections);
+    let exclusions: Vec<Item> = to_items(exclusions);
+    let optimization: Result<Optimization, _> = from_argument(optimization, "optimization", CatalogError::bad_argument);
+
+    optimization
+        .and_then(|optimization| CatalogState::from_jsvalue(catalog_state).and_then(|state| state.optimize(&selections, &exclusions, &optimization)))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = validateOutfitWasm)]
+pub fn validate_outfit(catalog_state: &JsValue, outfit: &JsValue) -> js_sys::Promise {
+    let outfit: Vec<Item> = to_items(outfit);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.validate_outfit(&outfit))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = repairOutfitWasm)]
+pub fn repair_outfit(catalog_state: &JsValue, outfit: &JsValue, pinned: &JsValue) -> js_sys::Promise {
+    let outfit: Vec<Item> = to_items(outfit);
+    let pinned: Vec<Item> = to_items(pinned);
+
+    CatalogState::from_jsvalue(catalog_state)
+        .and_then(|state| state.repair_outfit(&outfit, &pinned))
+        .into_promise()
+}
+
 #[wasm_bindgen(js_name = buildCatalogWasm)]
 pub fn build_catalog(assembly: &JsValue) -> js_sys::Promise {
     let assembly: CatalogAssembly = assembly.into_serde().unwrap();
@@ -53,6 +174,31 @@ pub fn build_catalog(assembly: &JsValue) -> js_sys::Promise {
         .into_promise()
 }
 
+#[wasm_bindgen(js_name = buildCatalogWithReportWasm)]
+pub fn build_catalog_with_report(assembly: &JsValue) -> js_sys::Promise {
+    let assembly: Result<CatalogAssembly, _> = from_argument(assembly, "assembly", CatalogBuilderError::bad_argument);
+
+    assembly
+        .and_then(catalog_builder::build_catalog_with_report)
+        .map(|(catalog, report)| (CatalogState::from_catalog(catalog), report))
+        .into_promise()
+}
+
+#[wasm_bindgen(js_name = dryRunEditWasm)]
+pub fn dry_run_edit(assembly: &JsValue, edit: &JsValue) -> js_sys::Promise {
+    let assembly: Result<CatalogAssembly, _> = from_argument(assembly, "assembly", CatalogBuilderError::bad_argument);
+    let edit: Result<AssemblyEdit, _> = from_argument(edit, "edit", CatalogBuilderError::bad_argument);
+
+    assembly
+        .and_then(|assembly| edit.and_then(|edit| catalog_builder::dry_run(&assembly, &edit)))
+        .into_promise()
+}
+
+fn from_argument<T: DeserializeOwned, E>(value: &JsValue, argument: &str, bad_argument: fn(&str) -> E) -> Result<T, E> {
+    value.into_serde()
+        .map_err(|_| bad_argument(argument))
+}
+
 fn to_items(items: &JsValue) -> Vec<Item> {
     let items: Vec<Item> = items.into_serde().unwrap();
 
@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+use std::mem;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use weave::zdd2::Forest;
+
+use catalog::{Catalog, CatalogError, CatalogState, OptionsByFamily, group_statuses};
+use types::Item;
+
+use to_items;
+
+/// Keeps the state the session started from, so the state it hands back has the same token, constraints and version.
+/// The outfits left by the choices are kept too: new choices narrow them in place,
+/// and only dropping a choice restricts the catalog's outfits again.
+#[wasm_bindgen]
+pub struct CatalogSession {
+    state: CatalogState,
+    catalog: Catalog,
+    required: HashSet<Item>,
+    outfits: Forest<Item>,
+    selections: Vec<Item>,
+    exclusions: Vec<Item>,
+}
+
+impl CatalogSession {
+    pub fn from_state(state: CatalogState) -> Result<Self, CatalogError> {
+        let catalog = state.catalog()?;
+        let required = catalog.required_items();
+        let mut session = CatalogSession {
+            selections: state.selections().to_vec(),
+            exclusions: state.exclusions().to_vec(),
+            outfits: Forest::many(&[]),
+            state,
+            catalog,
+            required,
+        };
+        session.restrict_again();
+
+        Ok(session)
+    }
+
+    pub fn item_options(&self) -> OptionsByFamily {
+        group_statuses(self.catalog.restricted_item_statuses(&self.outfits, &self.required, &self.selections, &self.exclusions))
+    }
+
+    pub fn remaining_outfits(&self) -> Vec<Vec<Item>> {
+        self.outfits.trees()
+    }
+
+    pub fn select_items(&mut self, items: &[Item]) -> Result<(), CatalogError> {
+        self.catalog.validate_items(items, &[])?;
+
+        self.selections = CatalogState::chain(&self.selections, items);
+        self.outfits = mem::replace(&mut self.outfits, Forest::many(&[])).subset_all(items);
+        Ok(())
+    }
+
+    pub fn deselect_items(&mut self, items: &[Item]) {
+        self.selections.retain(|item| !items.contains(item));
+        self.restrict_again();
+    }
+
+    pub fn exclude_items(&mut self, items: &[Item]) -> Result<(), CatalogError> {
+        self.catalog.validate_items(&[], items)?;
+
+        self.exclusions = CatalogState::chain(&self.exclusions, items);
+        self.outfits = mem::replace(&mut self.outfits, Forest::many(&[])).subset_none(items);
+        Ok(())
+    }
+
+    pub fn include_items(&mut self, items: &[Item]) {
+        self.exclusions.retain(|item| !items.contains(item));
+        self.restrict_again();
+    }
+
+    pub fn state(&self) -> CatalogState {
+        self.state.clone().with_choices(self.selections.clone(), self.exclusions.clone())
+    }
+
+    fn restrict_again(&mut self) {
+        self.outfits = self.catalog.forest().clone()
+            .subset_all(&self.selections)
+            .subset_none(&self.exclusions);
+    }
+}
+
+#[wasm_bindgen]
+impl CatalogSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(catalog_state: &JsValue) -> Result<CatalogSession, JsValue> {
+        CatalogState::from_jsvalue(catalog_state)
+            .and_then(Self::from_state)
+            .map_err(|err| to_js(&err))
+    }
+
+    pub fn options(&self) -> JsValue {
+        to_js(&self.item_options())
+    }
+
+    pub fn outfits(&self) -> JsValue {
+        to_js(&self.remaining_outfits())
+    }
+
+    pub fn select(&mut self, items: &JsValue) -> Result<(), JsValue> {
+        self.select_items(&to_items(items)).map_err(|err| to_js(&err))
+    }
+
+    pub fn deselect(&mut self, items: &JsValue) {
+        self.deselect_items(&to_items(items));
+    }
+
+    pub fn exclude(&mut self, items: &JsValue) -> Result<(), JsValue> {
+        self.exclude_items(&to_items(items)).map_err(|err| to_js(&err))
+    }
+
+    pub fn include(&mut self, items: &JsValue) {
+        self.include_items(&to_items(items));
+    }
+
+    #[wasm_bindgen(js_name = toState)]
+    pub fn to_state(&self) -> JsValue {
+        to_js(&self.state())
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    JsValue::from_serde(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::CatalogState;
+    use catalog::fixtures::{catalog, items, sorted_outfits};
+    use types::ItemStatus;
+
+    use super::CatalogSession;
+
+    fn session() -> CatalogSession {
+        let catalog = catalog(&[
+            &["pants:jeans", "shirts:blue", "shoes:boots"],
+            &["pants:jeans", "shirts:red", "shoes:boots"],
+            &["pants:slacks", "shirts:blue", "shoes:loafers"],
+            &["pants:slacks", "shirts:red", "shoes:boots"],
+        ]);
+
+        CatalogSession::from_state(CatalogState::from_catalog(catalog)).expect("expected session to start")
+    }
+
+    #[test]
+    fn choices_narrow_the_options_and_outfits() {
+        let mut session = session();
+        session.select_items(&items(&["shirts:blue"])).unwrap();
+        session.exclude_items(&items(&["shoes:loafers"])).unwrap();
+
+        assert_eq!(vec![items(&["pants:jeans", "shirts:blue", "shoes:boots"])], session.remaining_outfits());
+
+        let options = session.item_options();
+        assert_eq!(
+            vec![ItemStatus::Implied("pants:jeans".into()), ItemStatus::Excluded("pants:slacks".into())],
+            options["pants"]
+        );
+        assert_eq!(
+            vec![ItemStatus::Implied("shoes:boots".into()), ItemStatus::UserExcluded("shoes:loafers".into())],
+            options["shoes"]
+        );
+    }
+
+    #[test]
+    fn dropped_choices_widen_the_outfits_again() {
+        let mut session = session();
+        session.select_items(&items(&["shirts:blue"])).unwrap();
+        session.exclude_items(&items(&["shoes:loafers"])).unwrap();
+        session.deselect_items(&items(&["shirts:blue"]));
+
+        assert_eq!(
+            vec![
+                items(&["pants:jeans", "shirts:blue", "shoes:boots"]),
+                items(&["pants:jeans", "shirts:red", "shoes:boots"]),
+                items(&["pants:slacks", "shirts:red", "shoes:boots"]),
+            ],
+            sorted_outfits(session.remaining_outfits())
+        );
+
+        session.include_items(&items(&["shoes:loafers"]));
+        assert_eq!(4, session.remaining_outfits().len());
+    }
+
+    #[test]
+    fn unknown_choices_are_rejected() {
+        let mut session = session();
+
+        assert!(session.select_items(&items(&["shirts:green"])).is_err());
+        assert!(session.exclude_items(&items(&["hats:cap"])).is_err());
+        assert_eq!(4, session.remaining_outfits().len());
+    }
+
+    #[test]
+    fn state_keeps_the_choices_made() {
+        let mut session = session();
+        session.select_items(&items(&["shirts:red", "pants:slacks"])).unwrap();
+        session.exclude_items(&items(&["shoes:loafers"])).unwrap();
+        session.deselect_items(&items(&["shirts:red"]));
+
+        let state = session.state();
+        assert_eq!(&items(&["pants:slacks"])[..], state.selections());
+        assert_eq!(&items(&["shoes:loafers"])[..], state.exclusions());
+
+        let restarted = CatalogSession::from_state(state).unwrap();
+        assert_eq!(sorted_outfits(session.remaining_outfits()), sorted_outfits(restarted.remaining_outfits()));
+    }
+}
@@ -0,0 +1,147 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json;
+
+use super::{next_version, valid_id, CatalogRecord, CatalogStore, Expected, StoreError};
+
+static TEMPORARY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Keeps each version of a catalog as `<root>/<project_id>/<catalog_id>/<version>.json`.
+///
+/// A version file is claimed by hard-linking it into place, which fails when the file already exists,
+/// so stores in different processes (the server and the CLI) can share a root without overwriting each other.
+pub struct FileCatalogStore {
+    root: PathBuf,
+}
+
+impl FileCatalogStore {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        FileCatalogStore { root: root.into() }
+    }
+
+    fn project_dir(&self, project_id: &str) -> Result<PathBuf, StoreError> {
+        Ok(self.root.join(valid_id(project_id)?))
+    }
+
+    fn catalog_dir(&self, project_id: &str, catalog_id: &str) -> Result<PathBuf, StoreError> {
+        Ok(self.project_dir(project_id)?.join(valid_id(catalog_id)?))
+    }
+
+    /// The stored versions of a catalog in ascending order, empty when it was never stored.
+    fn versions(&self, project_id: &str, catalog_id: &str) -> Result<Vec<u64>, StoreError> {
+        let catalog_dir = self.catalog_dir(project_id, catalog_id)?;
+        if !catalog_dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut versions = fs::read_dir(catalog_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |extension| extension == "json"))
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()))
+            .collect::<Vec<u64>>();
+        versions.sort();
+
+        Ok(versions)
+    }
+
+    fn read(&self, project_id: &str, catalog_id: &str, version: u64) -> Result<CatalogRecord, StoreError> {
+        let path = self.catalog_dir(project_id, catalog_id)?.join(format!("{}.json", version));
+        let contents = fs::read(path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => StoreError::VersionNotFound { catalog_id: catalog_id.into(), version },
+                _ => StoreError::from(err),
+            })?;
+
+        serde_json::from_slice(&contents)
+            .map_err(|err| StoreError::Io { detail: err.to_string() })
+    }
+}
+
+impl CatalogStore for FileCatalogStore {
+    /// Writes go to a temporary file first, so readers never see a partly written catalog.
+    /// Losing the race for a version to another writer checks `expected` again against the new latest version.
+    fn put(&self, record: CatalogRecord, expected: Expected) -> Result<CatalogRecord, StoreError> {
+        let catalog_dir = self.catalog_dir(&record.project_id, &record.catalog_id)?;
+        fs::create_dir_all(&catalog_dir)?;
+
+        loop {
+            let current = self.versions(&record.project_id, &record.catalog_id)?.pop();
+            let version = next_version(&record.catalog_id, current, expected)?;
+
+            let stamped = record.clone().stamp(version);
+            let contents = serde_json::to_vec(&stamped)
+                .map_err(|err| StoreError::Io { detail: err.to_string() })?;
+            let temporary = catalog_dir.join(format!(
+                ".{}-{}.tmp",
+                process::id(),
+                TEMPORARY_FILES.fetch_add(1, Ordering::SeqCst)
+            ));
+
+            fs::write(&temporary, contents)?;
+            let claimed = fs::hard_link(&temporary, catalog_dir.join(format!("{}.json", version)));
+            fs::remove_file(&temporary)?;
+
+            match claimed {
+                Ok(()) => return Ok(stamped),
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(StoreError::from(err)),
+            }
+        }
+    }
+
+    fn get(&self, project_id: &str, catalog_id: &str) -> Result<CatalogRecord, StoreError> {
+        let version = self.versions(project_id, catalog_id)?
+            .pop()
+            .ok_or_else(|| StoreError::NotFound { catalog_id: catalog_id.into() })?;
+
+        self.read(project_id, catalog_id, version)
+    }
+
+    fn get_version(&self, project_id: &str, catalog_id: &str, version: u64) -> Result<CatalogRecord, StoreError> {
+        if !self.catalog_dir(project_id, catalog_id)?.is_dir() {
+            return Err(StoreError::NotFound { catalog_id: catalog_id.into() });
+        }
+
+        self.read(project_id, catalog_id, version)
+    }
+
+    fn history(&self, project_id: &str, catalog_id: &str) -> Result<Vec<CatalogRecord>, StoreError> {
+        let versions = self.versions(project_id, catalog_id)?;
+        if versions.is_empty() {
+            return Err(StoreError::NotFound { catalog_id: catalog_id.into() });
+        }
+
+        versions.iter()
+            .map(|&version| self.read(project_id, catalog_id, version))
+            .collect()
+    }
+
+    fn list(&self, project_id: &str) -> Result<Vec<CatalogRecord>, StoreError> {
+        let project_dir = self.project_dir(project_id)?;
+        if !project_dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut catalog_ids = fs::read_dir(project_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(String::from))
+            .collect::<Vec<_>>();
+        catalog_ids.sort();
+
+        // A directory without versions is left over from a write that never finished.
+        let mut records = vec![];
+        for catalog_id in catalog_ids {
+            if let Some(version) = self.versions(project_id, &catalog_id)?.pop() {
+                records.push(self.read(project_id, &catalog_id, version)?);
+            }
+        }
+
+        Ok(records)
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{next_version, valid_id, CatalogRecord, CatalogStore, Expected, StoreError};
+
+/// Keeps every version of each catalog, oldest first.
+#[derive(Default)]
+pub struct MemoryCatalogStore {
+    records: RwLock<BTreeMap<(String, String), Vec<CatalogRecord>>>,
+}
+
+impl MemoryCatalogStore {
+    fn key(project_id: &str, catalog_id: &str) -> Result<(String, String), StoreError> {
+        Ok((valid_id(project_id)?.to_string(), valid_id(catalog_id)?.to_string()))
+    }
+}
+
+impl CatalogStore for MemoryCatalogStore {
+    fn put(&self, record: CatalogRecord, expected: Expected) -> Result<CatalogRecord, StoreError> {
+        let key = Self::key(&record.project_id, &record.catalog_id)?;
+
+        let mut records = self.records.write().unwrap();
+        let current = records.get(&key)
+            .and_then(|versions| versions.last())
+            .map(|record| record.version);
+        let version = next_version(&record.catalog_id, current, expected)?;
+
+        let record = record.stamp(version);
+        records.entry(key).or_insert_with(Vec::new).push(record.clone());
+        Ok(record)
+    }
+
+    fn get(&self, project_id: &str, catalog_id: &str) -> Result<CatalogRecord, StoreError> {
+        let key = Self::key(project_id, catalog_id)?;
+
+        self.records.read().unwrap()
+            .get(&key)
+            .and_then(|versions| versions.last())
+            .cloned()
+            .ok_or_else(|| StoreError::NotFound { catalog_id: catalog_id.into() })
+    }
+
+    fn get_version(&self, project_id: &str, catalog_id: &str, version: u64) -> Result<CatalogRecord, StoreError> {
+        let key = Self::key(project_id, catalog_id)?;
+
+        let records = self.records.read().unwrap();
+        let versions = records.get(&key)
+            .ok_or_else(|| StoreError::NotFound { catalog_id: catalog_id.into() })?;
+
+        versions.iter()
+            .find(|record| record.version == version)
+            .cloned()
+            .ok_or_else(|| StoreError::VersionNotFound { catalog_id: catalog_id.into(), version })
+    }
+
+    fn history(&self, project_id: &str, catalog_id: &str) -> Result<Vec<CatalogRecord>, StoreError> {
+        let key = Self::key(project_id, catalog_id)?;
+
+        self.records.read().unwrap()
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| StoreError::NotFound { catalog_id: catalog_id.into() })
+    }
+
+    fn list(&self, project_id: &str) -> Result<Vec<CatalogRecord>, StoreError> {
+        let project_id = valid_id(project_id)?;
+
+        let records = self.records.read().unwrap()
+            .iter()
+            .filter(|((project, _), _)| project == project_id)
+            .filter_map(|(_, versions)| versions.last())
+            .cloned()
+            .collect();
+
+        Ok(records)
+    }
+}
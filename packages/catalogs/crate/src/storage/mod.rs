@@ -0,0 +1,356 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use catalog::{CatalogState, CatalogVersion};
+use catalog_builder::CatalogAssembly;
+use errors::{CatalogsError, StatusClass};
+
+pub use self::file::FileCatalogStore;
+pub use self::memory::MemoryCatalogStore;
+
+mod file;
+mod memory;
+
+/// A catalog as it is kept in a store: the assembly it was built from, the state built from it,
+/// and the version and content hash the store gave it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CatalogRecord {
+    project_id: String,
+    catalog_id: String,
+    version: u64,
+    hash: String,
+    created: String,
+    assembly: CatalogAssembly,
+    state: CatalogState,
+}
+
+impl CatalogRecord {
+    pub fn new(project_id: &str, catalog_id: &str, created: String, assembly: CatalogAssembly, state: CatalogState) -> Self {
+        CatalogRecord {
+            project_id: project_id.into(),
+            catalog_id: catalog_id.into(),
+            version: 0,
+            hash: state.content_hash(),
+            created,
+            assembly,
+            state,
+        }
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    pub fn catalog_id(&self) -> &str {
+        &self.catalog_id
+    }
+
+    /// Versions start at 1 and grow by one with every `put`; 0 means the record was never stored.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn created(&self) -> &str {
+        &self.created
+    }
+
+    pub fn assembly(&self) -> &CatalogAssembly {
+        &self.assembly
+    }
+
+    pub fn state(&self) -> &CatalogState {
+        &self.state
+    }
+
+    /// Gives the record its version, and records it in its state so sessions know what they were built from.
+    fn stamp(self, version: u64) -> Self {
+        let state = self.state.with_version(CatalogVersion::new(version, &self.hash));
+        CatalogRecord { version, state, ..self }
+    }
+}
+
+/// What a `put` expects to find in the store before it writes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(tag = "type", content = "version")]
+pub enum Expected {
+    Missing,
+    Any,
+    Version(u64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum StoreError {
+    AlreadyExists { catalog_id: String },
+    NotFound { catalog_id: String },
+    VersionNotFound { catalog_id: String, version: u64 },
+    InvalidId { id: String },
+    VersionConflict { catalog_id: String, expected: u64, actual: u64 },
+    Io { detail: String },
+}
+
+impl From<io::Error> for StoreError {
+    fn from(err: io::Error) -> Self {
+        StoreError::Io { detail: err.to_string() }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::AlreadyExists { catalog_id } => write!(f, "catalog '{}' already exists", catalog_id),
+            StoreError::NotFound { catalog_id } => write!(f, "catalog '{}' was not found", catalog_id),
+            StoreError::VersionNotFound { catalog_id, version } => write!(f, "catalog '{}' has no version {}", catalog_id, version),
+            StoreError::InvalidId { id } => write!(f, "'{}' is not a valid id", id),
+            StoreError::VersionConflict { catalog_id, expected, actual } =>
+                write!(f, "catalog '{}' is at version {}, not {}", catalog_id, actual, expected),
+            StoreError::Io { detail } => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+impl CatalogsError for StoreError {
+    fn code(&self) -> &'static str {
+        match self {
+            StoreError::AlreadyExists { .. } => "CATALOG_ALREADY_EXISTS",
+            StoreError::NotFound { .. } => "CATALOG_NOT_FOUND",
+            StoreError::VersionNotFound { .. } => "CATALOG_VERSION_NOT_FOUND",
+            StoreError::InvalidId { .. } => "INVALID_ID",
+            StoreError::VersionConflict { .. } => "VERSION_CONFLICT",
+            StoreError::Io { .. } => "STORAGE_FAILURE",
+        }
+    }
+
+    fn status(&self) -> StatusClass {
+        match self {
+            StoreError::AlreadyExists { .. } => StatusClass::AlreadyExists,
+            StoreError::NotFound { .. } | StoreError::VersionNotFound { .. } => StatusClass::NotFound,
+            StoreError::InvalidId { .. } => StatusClass::InvalidArgument,
+            StoreError::VersionConflict { .. } => StatusClass::FailedPrecondition,
+            StoreError::Io { .. } => StatusClass::Internal,
+        }
+    }
+}
+
+/// Where catalogs are kept, keyed by project and catalog id.
+/// Every version of a catalog is kept; `get` and `list` only see the latest.
+pub trait CatalogStore: Send + Sync {
+    /// Stores `record` as the next version of its catalog, provided the store holds what `expected` says.
+    /// Returns the record as it was stored.
+    fn put(&self, record: CatalogRecord, expected: Expected) -> Result<CatalogRecord, StoreError>;
+
+    fn get(&self, project_id: &str, catalog_id: &str) -> Result<CatalogRecord, StoreError>;
+
+    fn get_version(&self, project_id: &str, catalog_id: &str, version: u64) -> Result<CatalogRecord, StoreError>;
+
+    /// Every version of the catalog, oldest first.
+    fn history(&self, project_id: &str, catalog_id: &str) -> Result<Vec<CatalogRecord>, StoreError>;
+
+    fn list(&self, project_id: &str) -> Result<Vec<CatalogRecord>, StoreError>;
+
+    /// Stores an earlier version again as the latest one. Versions only ever grow,
+    /// so rolling back to version 2 of a catalog at version 5 creates version 6.
+    fn rollback(&self, project_id: &str, catalog_id: &str, version: u64, expected: Expected) -> Result<CatalogRecord, StoreError> {
+        let record = self.get_version(project_id, catalog_id, version)?;
+        self.put(record, expected)
+    }
+}
+
+/// The version a `put` should write, given the version currently stored.
+fn next_version(catalog_id: &str, current: Option<u64>, expected: Expected) -> Result<u64, StoreError> {
+    match (expected, current) {
+        (Expected::Missing, Some(_)) => Err(StoreError::AlreadyExists { catalog_id: catalog_id.into() }),
+        (Expected::Version(_), None) => Err(StoreError::NotFound { catalog_id: catalog_id.into() }),
+        (Expected::Version(expected), Some(actual)) if expected != actual => Err(StoreError::VersionConflict {
+            catalog_id: catalog_id.into(),
+            expected,
+            actual,
+        }),
+        (_, current) => Ok(current.unwrap_or(0) + 1),
+    }
+}
+
+/// Ids may become path components, so only plain names are accepted.
+fn valid_id(id: &str) -> Result<&str, StoreError> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+
+    if valid {
+        Ok(id)
+    } else {
+        Err(StoreError::InvalidId { id: id.into() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::thread;
+
+    use catalog::{CatalogState, CatalogVersion};
+    use catalog_builder::{build_catalog, CatalogAssembly};
+
+    use super::{CatalogRecord, CatalogStore, Expected, FileCatalogStore, MemoryCatalogStore, StoreError};
+
+    /// A directory for a file store, removed when the test ends even if it fails.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            TempDir(env::temp_dir().join(format!("ignition-catalogs-{}-{}", name, process::id())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn record(project_id: &str, catalog_id: &str) -> CatalogRecord {
+        record_with_items(project_id, catalog_id, &["shirts:red", "shirts:blue"])
+    }
+
+    fn record_with_items(project_id: &str, catalog_id: &str, items: &[&str]) -> CatalogRecord {
+        let assembly = CatalogAssembly::new(
+            btreemap! { "shirts".into() => items.iter().map(|&item| item.into()).collect() },
+            vec![],
+            vec![],
+        );
+        let state = CatalogState::from_catalog(build_catalog(assembly.clone()).unwrap());
+
+        CatalogRecord::new(project_id, catalog_id, "2018-12-01T00:00:00+00:00".into(), assembly, state)
+    }
+
+    fn catalog_ids(records: Vec<CatalogRecord>) -> Vec<String> {
+        records.iter().map(|record| record.catalog_id().into()).collect()
+    }
+
+    fn versions(records: Vec<CatalogRecord>) -> Vec<u64> {
+        records.iter().map(|record| record.version()).collect()
+    }
+
+    fn exercise(store: &dyn CatalogStore) {
+        assert_eq!(1, store.put(record("project", "summer"), Expected::Missing).unwrap().version());
+        store.put(record("project", "winter"), Expected::Missing).unwrap();
+        store.put(record("other", "summer"), Expected::Missing).unwrap();
+
+        assert_eq!(
+            StoreError::AlreadyExists { catalog_id: "summer".into() },
+            store.put(record("project", "summer"), Expected::Missing).unwrap_err()
+        );
+        assert_eq!(2, store.put(record("project", "summer"), Expected::Any).unwrap().version());
+        assert_eq!(
+            StoreError::VersionConflict { catalog_id: "summer".into(), expected: 1, actual: 2 },
+            store.put(record("project", "summer"), Expected::Version(1)).unwrap_err()
+        );
+        assert_eq!(3, store.put(record("project", "summer"), Expected::Version(2)).unwrap().version());
+        assert_eq!(
+            StoreError::NotFound { catalog_id: "spring".into() },
+            store.put(record("project", "spring"), Expected::Version(1)).unwrap_err()
+        );
+
+        assert_eq!(3, store.get("project", "summer").unwrap().version());
+        assert_eq!(
+            StoreError::NotFound { catalog_id: "spring".into() },
+            store.get("project", "spring").unwrap_err()
+        );
+
+        assert_eq!(vec!["summer", "winter"], catalog_ids(store.list("project").unwrap()));
+        assert!(store.list("empty").unwrap().is_empty());
+
+        assert_eq!(
+            StoreError::InvalidId { id: "../summer".into() },
+            store.get("project", "../summer").unwrap_err()
+        );
+    }
+
+    fn exercise_history(store: &dyn CatalogStore) {
+        let first = store.put(record_with_items("project", "summer", &["shirts:red"]), Expected::Missing).unwrap();
+        let second = store.put(record("project", "summer"), Expected::Version(1)).unwrap();
+        assert_ne!(first.hash(), second.hash());
+
+        assert_eq!(vec![1, 2], versions(store.history("project", "summer").unwrap()));
+        assert_eq!(first.hash(), store.get_version("project", "summer", 1).unwrap().hash());
+        assert_eq!(
+            StoreError::VersionNotFound { catalog_id: "summer".into(), version: 3 },
+            store.get_version("project", "summer", 3).unwrap_err()
+        );
+
+        let rolled_back = store.rollback("project", "summer", 1, Expected::Version(2)).unwrap();
+        assert_eq!(3, rolled_back.version());
+        assert_eq!(first.hash(), rolled_back.hash());
+        assert_eq!(vec![1, 2, 3], versions(store.history("project", "summer").unwrap()));
+
+        let state = store.get("project", "summer").unwrap().state().clone();
+        assert_eq!(Some(&CatalogVersion::new(3, first.hash())), state.version());
+    }
+
+    #[test]
+    fn memory_store_keeps_catalogs_per_project() {
+        exercise(&MemoryCatalogStore::default());
+    }
+
+    #[test]
+    fn memory_store_keeps_every_version() {
+        exercise_history(&MemoryCatalogStore::default());
+    }
+
+    #[test]
+    fn file_store_keeps_catalogs_per_project() {
+        let root = TempDir::new("projects");
+        exercise(&FileCatalogStore::new(&root.0));
+    }
+
+    #[test]
+    fn file_store_lists_only_stored_catalogs() {
+        let root = TempDir::new("list");
+        let store = FileCatalogStore::new(&root.0);
+        store.put(record("project", "summer"), Expected::Missing).unwrap();
+        fs::create_dir_all(root.0.join("project").join("autumn")).unwrap();
+
+        assert_eq!(vec!["summer"], catalog_ids(store.list("project").unwrap()));
+    }
+
+    #[test]
+    fn file_store_keeps_every_version() {
+        let root = TempDir::new("history");
+        exercise_history(&FileCatalogStore::new(&root.0));
+    }
+
+    #[test]
+    fn file_stores_sharing_a_root_keep_every_version() {
+        let root = TempDir::new("shared");
+        let writers = (0..4)
+            .map(|_| {
+                let store = FileCatalogStore::new(&root.0);
+                thread::spawn(move || {
+                    for _ in 0..5 {
+                        store.put(record("project", "summer"), Expected::Any).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = FileCatalogStore::new(&root.0);
+        assert_eq!((1..21).collect::<Vec<u64>>(), versions(store.history("project", "summer").unwrap()));
+        assert_eq!(
+            Err(StoreError::AlreadyExists { catalog_id: "summer".into() }),
+            store.put(record("project", "summer"), Expected::Missing).map(|record| record.version())
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+
 pub type Family = String;
 pub type Item = String;
+pub type Attributes = BTreeMap<String, String>;
+pub type NumericAttributes = BTreeMap<String, f64>;
 
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", content = "item")]
@@ -8,4 +12,26 @@ pub enum ItemStatus<T> {
     Excluded(T),
     Available(T),
     Selected(T),
+    UserExcluded(T),
+    Implied(T),
+}
+
+impl<T> ItemStatus<T> {
+    pub fn item(&self) -> &T {
+        match self {
+            ItemStatus::Required(item)
+            | ItemStatus::Excluded(item)
+            | ItemStatus::Available(item)
+            | ItemStatus::Selected(item)
+            | ItemStatus::UserExcluded(item)
+            | ItemStatus::Implied(item) => item,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum FamilyStatus {
+    Decided,
+    Open,
+    Exhausted,
 }
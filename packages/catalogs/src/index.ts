@@ -10,36 +10,94 @@ export type CatalogState = {
     readonly token: CatalogToken;
     readonly selections: Item[];
     readonly exclusions: Item[];
+    readonly constraints?: AggregateConstraint[];
+    readonly version?: CatalogVersion;
+};
+
+export type CatalogVersion = {
+    readonly version: number;
+    readonly hash: string;
 };
 
 export type CatalogBuildError =
     { type: "EmptyCatalog" }
+    | { type: "BadArgument", argument: string }
     | { type: "InclusionMissingFamily", item: string }
     | { type: "ExclusionMissingFamily", item: string }
     | { type: "MultipleFamiliesRegistered", item: string, families: string[] }
     | { type: "InclusionFamilyConflict", family: string, items: string[] }
     | { type: "ExclusionFamilyConflict", family: string, items: string[] }
+    | { type: "AttributeMissingFamily", item: string }
+    | { type: "UnmatchedAttribute", attribute: string, value: string }
+    | { type: "AttributeRuleMissingFamily", family: string }
+    | { type: "AttributeRuleFamilyConflict", family: string }
+    | { type: "AttributeRuleUnknownAttribute", attribute: string }
+    | { type: "NumericAttributeMissingFamily", item: string }
+    | { type: "AggregateRuleMissingFamily", family: string }
+    | { type: "AggregateRuleUnknownAttribute", attribute: string }
     | { type: "CompoundError", errors: CatalogBuildError[] }
+    | { type: "UnknownExclusionRule", index: number }
+    | { type: "UnknownInclusionRule", index: number }
+    | { type: "UnknownItem", item: Item }
 
 export type CatalogOptionsError =
     { type: "UnknownSelections", items: Item[] }
     | { type: "UnknownExclusions", items: Item[] }
     | { type: "UnknownItems", selections: Item[], exclusions: Item[] }
     | { type: "BadState" }
+    | { type: "BadArgument", argument: string }
     | { type: "BadToken", token: CatalogToken, detail: string }
+    | { type: "UnsupportedToken", token: CatalogToken, version: number }
+    | { type: "UnknownConstraints", families: Family[], attributes: string[] }
+    | { type: "UnknownAttribute", attribute: string }
 
 export type CatalogFamilies = {
     readonly [key: string]: Item[];
 }
 
-export type CatalogExclusionRule = {
-    readonly conditions: Item[];
-    readonly exclusions: Item[];
+export type CatalogAttributes = {
+    readonly [key: string]: {
+        readonly [key: string]: string;
+    };
+}
+
+export type AttributeTarget = {
+    readonly attribute: string;
+    readonly value: string;
+}
+
+export type RuleTarget = Item | AttributeTarget;
+
+export type CatalogExclusionRule<T extends RuleTarget = RuleTarget> = {
+    readonly conditions: T[];
+    readonly exclusions: T[];
+}
+
+export type CatalogInclusionRule<T extends RuleTarget = RuleTarget> = {
+    readonly conditions: T[];
+    readonly inclusions: T[];
+}
+
+export type AttributeRelation = "Equal" | "NotEqual";
+
+export type CatalogAttributeRule = {
+    readonly attribute: string;
+    readonly families: [Family, Family];
+    readonly relation: AttributeRelation;
+}
+
+export type CatalogNumericAttributes = {
+    readonly [key: string]: {
+        readonly [key: string]: number;
+    };
 }
 
-export type CatalogInclusionRule = {
-    readonly conditions: Item[];
-    readonly inclusions: Item[];
+export type AggregateConstraint = {
+    readonly attribute: string;
+    readonly aggregate: "Sum" | "Min" | "Max";
+    readonly comparison: "AtMost" | "AtLeast";
+    readonly bound: number | { family: Family, attribute: string };
+    readonly families?: Family[];
 }
 
 export type Options = {
@@ -50,7 +108,134 @@ export type ItemStatus =
     { type: "Available", item: Item }
     | { type: "Excluded", item: Item }
     | { type: "Selected", item: Item }
-    | { type: "Required", item: Item };
+    | { type: "Required", item: Item }
+    | { type: "UserExcluded", item: Item }
+    | { type: "Implied", item: Item };
+
+export type FamilyStatus = "Decided" | "Open" | "Exhausted";
+
+export type FamilyOptions = {
+    readonly status: FamilyStatus;
+    readonly remaining: number;
+    readonly items: ItemStatus[];
+}
+
+export type OptionsWithFamilies = {
+    readonly [key: string]: FamilyOptions;
+}
+
+export type ItemCount = {
+    readonly status: ItemStatus;
+    readonly count: number;
+}
+
+export type CountedOptions = {
+    readonly total: number;
+    readonly options: {
+        readonly [key: string]: ItemCount[];
+    };
+}
+
+export type PriceRange = {
+    readonly min: number;
+    readonly max: number;
+}
+
+export type ItemPrice = {
+    readonly status: ItemStatus;
+    readonly range: PriceRange | null;
+}
+
+export type PricedOptions = {
+    readonly [key: string]: ItemPrice[];
+}
+
+export type ItemConflicts = {
+    readonly status: ItemStatus;
+    readonly conflicts: Item[] | null;
+}
+
+export type ConflictOptions = {
+    readonly [key: string]: ItemConflicts[];
+}
+
+export type RebasedState = {
+    readonly state: CatalogState;
+    readonly missing_selections: Item[];
+    readonly missing_exclusions: Item[];
+    readonly conflicting_selections: Item[];
+    readonly dropped_constraints: AggregateConstraint[];
+}
+
+export type MovedItem = {
+    readonly item: Item;
+    readonly from: Family;
+    readonly to: Family;
+}
+
+export type RuleChanges<R> = {
+    readonly added: R[];
+    readonly removed: R[];
+}
+
+export type CatalogDiff = {
+    readonly added_families: Family[];
+    readonly removed_families: Family[];
+    readonly added_items: Item[];
+    readonly removed_items: Item[];
+    readonly moved_items: MovedItem[];
+    readonly exclusions: RuleChanges<ExpandedExclusionRule>;
+    readonly inclusions: RuleChanges<ExpandedInclusionRule>;
+    readonly outfits_before: number;
+    readonly outfits_after: number;
+    readonly outfits_gained: number;
+    readonly outfits_lost: number;
+    readonly gained_outfits: Item[][];
+    readonly lost_outfits: Item[][];
+}
+
+export type CatalogAssembly = {
+    readonly families: CatalogFamilies;
+    readonly exclusions: CatalogExclusionRule[];
+    readonly inclusions: CatalogInclusionRule[];
+    readonly attributes?: CatalogAttributes;
+    readonly attribute_rules?: CatalogAttributeRule[];
+    readonly numeric_attributes?: CatalogNumericAttributes;
+    readonly aggregate_rules?: AggregateConstraint[];
+}
+
+export type AssemblyEdit =
+    { type: "AddExclusion", rule: CatalogExclusionRule }
+    | { type: "RemoveExclusion", index: number }
+    | { type: "AddInclusion", rule: CatalogInclusionRule }
+    | { type: "RemoveInclusion", index: number }
+    | { type: "AddItem", family: Family, item: Item }
+    | { type: "RemoveItem", item: Item };
+
+export type EditImpact = {
+    readonly outfits_before: number;
+    readonly outfits_after: number;
+    readonly removed_outfits: number;
+    readonly added_outfits: number;
+    readonly dead_items: Item[];
+    readonly emptied_families: Family[];
+}
+
+export type RuleId =
+    { type: "Exclusion", index: number }
+    | { type: "Inclusion", index: number };
+
+export type RuleEffect = {
+    readonly rule: RuleId;
+    readonly removed_outfits: number;
+    readonly unique_outfits: number;
+    readonly overlapping: RuleId[];
+}
+
+export type RuleReport = {
+    readonly outfits: number;
+    readonly rules: RuleEffect[];
+}
 
 export type Item = string;
 export type Family = string;
@@ -58,9 +243,21 @@ export type Family = string;
 export function buildCatalog(
     families: CatalogFamilies,
     exclusions: CatalogExclusionRule[] = [],
-    inclusions: CatalogInclusionRule[] = []
+    inclusions: CatalogInclusionRule[] = [],
+    attributes: CatalogAttributes = {},
+    attributeRules: CatalogAttributeRule[] = [],
+    numericAttributes: CatalogNumericAttributes = {},
+    aggregateRules: AggregateConstraint[] = []
 ): NomadTE<IgnitionEffect, CatalogBuildError, CatalogState> {
-    let assembly = {families: families, exclusions: exclusions, inclusions: inclusions};
+    let assembly = {
+        families: families,
+        exclusions: exclusions,
+        inclusions: inclusions,
+        attributes: attributes,
+        attribute_rules: attributeRules,
+        numeric_attributes: numericAttributes,
+        aggregate_rules: aggregateRules
+    };
 
     return timed(`build_catalog`, {}, () =>
         tryCatch(
@@ -71,6 +268,31 @@ export function buildCatalog(
     );
 }
 
+export function buildCatalogWithReport(
+    assembly: CatalogAssembly
+): NomadTE<IgnitionEffect, CatalogBuildError, [CatalogState, RuleReport]> {
+    return timed(`build_catalog_with_report`, {}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.buildCatalogWithReportWasm(assembly)),
+            (err: any) => err
+        )
+    );
+}
+
+export function dryRunEdit(
+    assembly: CatalogAssembly,
+    edit: AssemblyEdit
+): NomadTE<IgnitionEffect, CatalogBuildError, EditImpact> {
+    return timed(`dry_run_edit`, {}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.dryRunEditWasm(assembly, edit)),
+            (err: any) => err
+        )
+    );
+}
+
 export type IgnitionOptionsResult = NomadTE<IgnitionEffect, CatalogOptionsError, [Options, CatalogState]>
 
 export function findOptions(
@@ -87,6 +309,115 @@ export function findOptions(
     );
 }
 
+export type IgnitionFamilyOptionsResult = NomadTE<IgnitionEffect, CatalogOptionsError, [OptionsWithFamilies, CatalogState]>
+
+export function findFamilyOptions(
+    catalogState: CatalogState,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): IgnitionFamilyOptionsResult {
+    return timed(`find_family_options`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.findFamilyOptionsWasm(catalogState, selections, exclusions)),
+            (err: any) => err
+        )
+    );
+}
+
+export type IgnitionCountedOptionsResult = NomadTE<IgnitionEffect, CatalogOptionsError, [CountedOptions, CatalogState]>
+
+export function findCountedOptions(
+    catalogState: CatalogState,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): IgnitionCountedOptionsResult {
+    return timed(`find_counted_options`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.findCountedOptionsWasm(catalogState, selections, exclusions)),
+            (err: any) => err
+        )
+    );
+}
+
+export type IgnitionPricedOptionsResult = NomadTE<IgnitionEffect, CatalogOptionsError, [PricedOptions, CatalogState]>
+
+export function findPricedOptions(
+    catalogState: CatalogState,
+    attribute: string,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): IgnitionPricedOptionsResult {
+    return timed(`find_priced_options`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.findPricedOptionsWasm(catalogState, selections, exclusions, attribute)),
+            (err: any) => err
+        )
+    );
+}
+
+export type IgnitionConflictOptionsResult = NomadTE<IgnitionEffect, CatalogOptionsError, [ConflictOptions, CatalogState]>
+
+export function findConflictOptions(
+    catalogState: CatalogState,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): IgnitionConflictOptionsResult {
+    return timed(`find_conflict_options`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.findConflictOptionsWasm(catalogState, selections, exclusions)),
+            (err: any) => err
+        )
+    );
+}
+
+export function rebaseState(
+    catalogState: CatalogState,
+    targetState: CatalogState
+): NomadTE<IgnitionEffect, CatalogOptionsError, RebasedState> {
+    return timed(`rebase_state`, {token: hashToken(targetState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.rebaseStateWasm(catalogState, targetState)),
+            (err: any) => err
+        )
+    );
+}
+
+export function diffCatalogs(
+    catalogState: CatalogState,
+    afterState: CatalogState
+): NomadTE<IgnitionEffect, CatalogOptionsError, CatalogDiff> {
+    return timed(`diff_catalogs`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.diffCatalogsWasm(catalogState, afterState)),
+            (err: any) => err
+        )
+    );
+}
+
+export type OptionsQuery = {
+    readonly selections?: Item[];
+    readonly exclusions?: Item[];
+}
+
+export function findOptionsBatch(
+    catalogState: CatalogState,
+    queries: OptionsQuery[]
+): NomadTE<IgnitionEffect, CatalogOptionsError, Options[]> {
+    return timed(`find_options_batch`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.findOptionsBatchWasm(catalogState, queries)),
+            (err: any) => err
+        )
+    );
+}
+
 export function findOutfits(
     catalogState: CatalogState,
     selections: Item[] = [],
@@ -101,6 +432,172 @@ export function findOutfits(
     );
 }
 
+export interface CatalogSession {
+    options(): Options;
+
+    outfits(): Item[][];
+
+    select(items: Item[]): void;
+
+    deselect(items: Item[]): void;
+
+    exclude(items: Item[]): void;
+
+    include(items: Item[]): void;
+
+    toState(): CatalogState;
+
+    free(): void;
+}
+
+export function openSession(
+    catalogState: CatalogState
+): NomadTE<IgnitionEffect, CatalogOptionsError, CatalogSession> {
+    return timed(`open_session`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => new m.CatalogSession(catalogState)),
+            (err: any) => err
+        )
+    );
+}
+
+export type FamilyRecommendation = {
+    readonly family: Family;
+    readonly entropy: number;
+    readonly choices: number;
+}
+
+export function recommendFamilies(
+    catalogState: CatalogState,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): NomadTE<IgnitionEffect, CatalogOptionsError, [FamilyRecommendation[], CatalogState]> {
+    return timed(`recommend_families`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.recommendFamiliesWasm(catalogState, selections, exclusions)),
+            (err: any) => err
+        )
+    );
+}
+
+export type RuleViolation<R> = {
+    readonly index: number;
+    readonly rule: R;
+}
+
+export type ExpandedExclusionRule = {
+    readonly conditions: Item[][];
+    readonly exclusions: Item[];
+}
+
+export type ExpandedInclusionRule = {
+    readonly conditions: Item[][];
+    readonly inclusions: Item[][];
+}
+
+export type ExpandedAttributeRule = {
+    readonly relation: AttributeRelation;
+    readonly left: { readonly [item: string]: string | null };
+    readonly right: { readonly [item: string]: string | null };
+}
+
+export type OutfitValidation = {
+    readonly valid: boolean;
+    readonly unknown_items: Item[];
+    readonly missing_families: Family[];
+    readonly duplicate_families: Family[];
+    readonly violated_exclusions: RuleViolation<ExpandedExclusionRule>[];
+    readonly violated_inclusions: RuleViolation<ExpandedInclusionRule>[];
+    readonly violated_attribute_rules: RuleViolation<ExpandedAttributeRule>[];
+    readonly violated_constraints: RuleViolation<AggregateConstraint>[];
+    readonly violated_query_constraints: RuleViolation<AggregateConstraint>[];
+}
+
+export function validateOutfit(
+    catalogState: CatalogState,
+    outfit: Item[]
+): NomadTE<IgnitionEffect, CatalogOptionsError, OutfitValidation> {
+    return timed(`validate_outfit`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.validateOutfitWasm(catalogState, outfit)),
+            (err: any) => err
+        )
+    );
+}
+
+export type Substitution = {
+    readonly family: Family;
+    readonly from: Item | null;
+    readonly to: Item;
+}
+
+export type RepairedOutfit = {
+    readonly outfit: Item[];
+    readonly substitutions: Substitution[];
+}
+
+export function repairOutfit(
+    catalogState: CatalogState,
+    outfit: Item[],
+    pinned: Item[] = []
+): NomadTE<IgnitionEffect, CatalogOptionsError, RepairedOutfit | null> {
+    return timed(`repair_outfit`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.repairOutfitWasm(catalogState, outfit, pinned)),
+            (err: any) => err
+        )
+    );
+}
+
+export type CompletionMode =
+    { type: "First" }
+    | { type: "Random", seed: number };
+
+export function completeOutfit(
+    catalogState: CatalogState,
+    selections: Item[] = [],
+    exclusions: Item[] = [],
+    mode: CompletionMode = {type: "First"}
+): NomadTE<IgnitionEffect, CatalogOptionsError, [Item[] | null, CatalogState]> {
+    return timed(`complete_outfit`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.completeOutfitWasm(catalogState, selections, exclusions, mode)),
+            (err: any) => err
+        )
+    );
+}
+
+export type Optimization = {
+    readonly attribute: string;
+    readonly objective: "Minimize" | "Maximize";
+    readonly limit?: number;
+}
+
+export type RankedOutfit = {
+    readonly outfit: Item[];
+    readonly score: number;
+}
+
+export function optimizeOutfits(
+    catalogState: CatalogState,
+    optimization: Optimization,
+    selections: Item[] = [],
+    exclusions: Item[] = []
+): NomadTE<IgnitionEffect, CatalogOptionsError, [RankedOutfit[], CatalogState]> {
+    return timed(`optimize_outfits`, {token: hashToken(catalogState)}, () =>
+        tryCatch(
+            () => import("../crate/pkg")
+                .then(m => m.optimizeOutfitsWasm(catalogState, selections, exclusions, optimization)),
+            (err: any) => err
+        )
+    );
+}
+
 function hashToken(catalogState: CatalogState): string {
     return require('crypto')
         .createHash('sha1')
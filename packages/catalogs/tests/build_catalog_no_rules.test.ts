@@ -1,5 +1,5 @@
 import test from "ava";
-import {buildCatalog, CatalogState, CatalogBuildError} from "../src";
+import {buildCatalog, CatalogState, CatalogBuildError, findOutfits} from "../src";
 import {left, right} from "fp-ts/lib/Either";
 
 test("build, when one item is in two families, gives an error", async t => {
@@ -38,9 +38,27 @@ test("build regression test v1", async t => {
     const [catalog] = await buildCatalog(families).run();
 
     const expectedCatalog: CatalogState = {
-        token: "MwAAAAAAAAAoMCAoMSAoTikgKDIgKDMgKE4pIChBKSkgKEEpKSkgKDIgKDMgKE4pIChBKSkgKEEpKSkEAAAAAAAAAAsAAAAAAAAAcGFudHM6amVhbnMCAAAAAAAAAAwAAAAAAAAAcGFudHM6c2xhY2tzAgAAAAAAAAALAAAAAAAAAHNoaXJ0czpibHVlAgAAAAAAAAAKAAAAAAAAAHNoaXJ0czpyZWQCAAAAAAAAAAQAAAAAAAAACwAAAAAAAABwYW50czpqZWFucwUAAAAAAAAAcGFudHMMAAAAAAAAAHBhbnRzOnNsYWNrcwUAAAAAAAAAcGFudHMLAAAAAAAAAHNoaXJ0czpibHVlBgAAAAAAAABzaGlydHMKAAAAAAAAAHNoaXJ0czpyZWQGAAAAAAAAAHNoaXJ0cw==",
+        token: "SUdOQwEzAAAAAAAAACgwICgxIChOKSAoMiAoMyAoTikgKEEpKSAoQSkpKSAoMiAoMyAoTikgKEEpKSAoQSkpKQQAAAAAAAAACwAAAAAAAABwYW50czpqZWFucwIAAAAAAAAADAAAAAAAAABwYW50czpzbGFja3MCAAAAAAAAAAsAAAAAAAAAc2hpcnRzOmJsdWUCAAAAAAAAAAoAAAAAAAAAc2hpcnRzOnJlZAIAAAAAAAAABAAAAAAAAAALAAAAAAAAAHBhbnRzOmplYW5zBQAAAAAAAABwYW50cwwAAAAAAAAAcGFudHM6c2xhY2tzBQAAAAAAAABwYW50cwsAAAAAAAAAc2hpcnRzOmJsdWUGAAAAAAAAAHNoaXJ0cwoAAAAAAAAAc2hpcnRzOnJlZAYAAAAAAAAAc2hpcnRzAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA==",
         selections: [],
         exclusions: [],
     };
     t.deepEqual(catalog, right(expectedCatalog));
 });
+
+test("build regression test v1, tokens from before the version prefix still decode", async t => {
+    const legacyCatalog: CatalogState = {
+        token: "MwAAAAAAAAAoMCAoMSAoTikgKDIgKDMgKE4pIChBKSkgKEEpKSkgKDIgKDMgKE4pIChBKSkgKEEpKSkEAAAAAAAAAAsAAAAAAAAAcGFudHM6amVhbnMCAAAAAAAAAAwAAAAAAAAAcGFudHM6c2xhY2tzAgAAAAAAAAALAAAAAAAAAHNoaXJ0czpibHVlAgAAAAAAAAAKAAAAAAAAAHNoaXJ0czpyZWQCAAAAAAAAAAQAAAAAAAAACwAAAAAAAABwYW50czpqZWFucwUAAAAAAAAAcGFudHMMAAAAAAAAAHBhbnRzOnNsYWNrcwUAAAAAAAAAcGFudHMLAAAAAAAAAHNoaXJ0czpibHVlBgAAAAAAAABzaGlydHMKAAAAAAAAAHNoaXJ0czpyZWQGAAAAAAAAAHNoaXJ0cw==",
+        selections: [],
+        exclusions: [],
+    };
+
+    const [outfits] = await findOutfits(legacyCatalog).run();
+
+    const expectedOutfits = [
+        ["pants:jeans", "shirts:blue"],
+        ["pants:jeans", "shirts:red"],
+        ["pants:slacks", "shirts:blue"],
+        ["pants:slacks", "shirts:red"]
+    ];
+    t.deepEqual(outfits, right(expectedOutfits));
+});
@@ -0,0 +1,73 @@
+import test from "ava";
+import {buildCatalog, CatalogOptionsError, CatalogState, openSession, Options} from "../src";
+import {left} from "fp-ts/lib/Either";
+
+const DEFAULT_STATE = {token: "", selections: [], exclusions: []};
+
+const families = {
+    "shirts": ["shirts:red", "shirts:blue"],
+    "pants": ["pants:jeans", "pants:slacks"],
+};
+const catalogState: Promise<CatalogState> = buildCatalog(families)
+    .fold(() => DEFAULT_STATE, res => res)
+    .run()
+    .then(n => n.value);
+
+test("session with no selections gives all outfits", async t => {
+    const [session] = await openSession(await catalogState).run();
+    const outfits = session.map(s => s.outfits()).getOrElse([]);
+
+    const expected = [
+        ["pants:jeans", "shirts:blue"],
+        ["pants:jeans", "shirts:red"],
+        ["pants:slacks", "shirts:blue"],
+        ["pants:slacks", "shirts:red"]
+    ];
+    t.deepEqual(outfits, expected);
+});
+
+test("session select and deselect update the options", async t => {
+    const [result] = await openSession(await catalogState).run();
+    const session = result.getOrElse(undefined as any);
+
+    session.select(["shirts:red"]);
+    const expectedSelected: Options = {
+        "shirts": [
+            {type: "Selected", item: "shirts:red"},
+            {type: "Excluded", item: "shirts:blue"}
+        ],
+        "pants": [
+            {type: "Available", item: "pants:jeans"},
+            {type: "Available", item: "pants:slacks"},
+        ]
+    };
+    t.deepEqual(session.options(), expectedSelected);
+    t.deepEqual(session.toState().selections, ["shirts:red"]);
+
+    session.deselect(["shirts:red"]);
+    const expectedDeselected: Options = {
+        "shirts": [
+            {type: "Available", item: "shirts:blue"},
+            {type: "Available", item: "shirts:red"}
+        ],
+        "pants": [
+            {type: "Available", item: "pants:jeans"},
+            {type: "Available", item: "pants:slacks"},
+        ]
+    };
+    t.deepEqual(session.options(), expectedDeselected);
+    t.deepEqual(session.toState().selections, []);
+
+    session.free();
+});
+
+test("session returns error when the catalog token is blank", async t => {
+    const [error] = await openSession(DEFAULT_STATE).run();
+
+    const expectedError: CatalogOptionsError = {
+        type: "BadToken",
+        token: "",
+        detail: "failed to fill whole buffer"
+    };
+    t.deepEqual(error, left(expectedError));
+});
@@ -22,7 +22,7 @@ test("findOptions has one effect for timing", async t => {
     t.deepEqual(effects[0].type, "Timed");
     t.deepEqual(effects[0].name, "find_options");
     // @ts-ignore
-    t.deepEqual(effects[0].details.token, "J8WFmwgHMh4Sh65iElxsr7f3Lx8=");
+    t.deepEqual(effects[0].details.token, "eUl1d1WlWJ+aKve0o9pX1/YFdh0=");
 });
 
 test("findOptions with no rules, and no selections", async t => {
@@ -101,8 +101,8 @@ test("findOptions with no rules, and one exclusion", async t => {
 
     const expectedOptions: Options = {
         "shirts": [
-            {type: "Required", item: "shirts:red"},
-            {type: "Excluded", item: "shirts:blue"}
+            {type: "Implied", item: "shirts:red"},
+            {type: "UserExcluded", item: "shirts:blue"}
         ],
         "pants": [
             {type: "Available", item: "pants:jeans"},
@@ -121,12 +121,12 @@ test("findOptions with no rules, and two exclusions", async t => {
 
     const expectedOptions: Options = {
         "shirts": [
-            {type: "Required", item: "shirts:blue"},
-            {type: "Excluded", item: "shirts:red"},
+            {type: "Implied", item: "shirts:blue"},
+            {type: "UserExcluded", item: "shirts:red"},
         ],
         "pants": [
-            {type: "Required", item: "pants:jeans"},
-            {type: "Excluded", item: "pants:slacks"},
+            {type: "Implied", item: "pants:jeans"},
+            {type: "UserExcluded", item: "pants:slacks"},
         ]
     };
     t.deepEqual(result1.map(e => e[0]), right(expectedOptions));
@@ -141,8 +141,8 @@ test("findOptions with no rules, and one exclusion", async t => {
 
     const expectedOptions: Options = {
         "shirts": [
-            {type: "Required", item: "shirts:red"},
-            {type: "Excluded", item: "shirts:blue"}
+            {type: "Implied", item: "shirts:red"},
+            {type: "UserExcluded", item: "shirts:blue"}
         ],
         "pants": [
             {type: "Selected", item: "pants:slacks"},